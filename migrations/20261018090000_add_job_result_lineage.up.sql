@@ -0,0 +1,17 @@
+-- Link job results to the run and task that produced them, and record load lineage
+ALTER TABLE job_results ADD COLUMN run_id UUID REFERENCES job_runs(run_id) ON DELETE CASCADE;
+ALTER TABLE job_results ADD COLUMN task_order INT;
+-- Position of the loader in its task's loader_config, so a retried run replaces a loader's
+-- result instead of adding another
+ALTER TABLE job_results ADD COLUMN loader_index INT;
+ALTER TABLE job_results ADD COLUMN loader_type VARCHAR(50);
+ALTER TABLE job_results ADD COLUMN bytes_written BIGINT;
+ALTER TABLE job_results ADD COLUMN duration_ms BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE job_results ADD COLUMN schema_hash VARCHAR(64);
+
+-- Table loaders (e.g. PostgreSQL) have no file to point at
+ALTER TABLE job_results ALTER COLUMN file_path DROP NOT NULL;
+ALTER TABLE job_results ALTER COLUMN created_at TYPE TIMESTAMPTZ;
+
+CREATE INDEX idx_job_results_run_id ON job_results(run_id);
+CREATE UNIQUE INDEX ux_job_results_loader ON job_results(run_id, task_order, loader_index);
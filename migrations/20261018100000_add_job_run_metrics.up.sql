@@ -0,0 +1,2 @@
+-- Per-task and per-loader outcome summary for each run
+ALTER TABLE job_runs ADD COLUMN metrics JSONB;
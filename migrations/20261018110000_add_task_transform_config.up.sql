@@ -0,0 +1,2 @@
+-- Optional declarative transform steps applied between extraction and loading
+ALTER TABLE task_definitions ADD COLUMN transform_config JSONB;
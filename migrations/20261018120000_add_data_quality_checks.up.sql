@@ -0,0 +1,20 @@
+-- Data quality checks declared per task, and their outcomes per run
+ALTER TABLE task_definitions ADD COLUMN checks_config JSONB;
+
+CREATE TABLE check_results (
+    id UUID PRIMARY KEY,
+    run_id UUID NOT NULL REFERENCES job_runs(run_id) ON DELETE CASCADE,
+    job_id UUID NOT NULL REFERENCES job_definitions(job_id) ON DELETE CASCADE,
+    task_order INT NOT NULL,
+    check_order INT NOT NULL,
+    check_name TEXT NOT NULL,
+    column_name TEXT,
+    severity TEXT NOT NULL,
+    passed BOOLEAN NOT NULL,
+    failing_rows BIGINT,
+    message TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (run_id, task_order, check_order)
+);
+
+CREATE INDEX idx_check_results_run_id ON check_results(run_id);
@@ -0,0 +1,2 @@
+-- Column masking policies applied before loading
+ALTER TABLE task_definitions ADD COLUMN masking_config JSONB;
@@ -0,0 +1,17 @@
+-- Schema of each task's extracted data per run, with drift against the previous successful run
+CREATE TABLE schema_snapshots (
+    id UUID PRIMARY KEY,
+    run_id UUID NOT NULL REFERENCES job_runs(run_id) ON DELETE CASCADE,
+    job_id UUID NOT NULL REFERENCES job_definitions(job_id) ON DELETE CASCADE,
+    task_order INT NOT NULL,
+    columns JSONB NOT NULL,
+    schema_hash TEXT NOT NULL,
+    drift JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (run_id, task_order)
+);
+
+CREATE INDEX idx_schema_snapshots_job_task ON schema_snapshots(job_id, task_order);
+
+-- Whether breaking drift against that snapshot fails the task instead of loading the data
+ALTER TABLE task_definitions ADD COLUMN fail_on_breaking_drift BOOLEAN NOT NULL DEFAULT FALSE;
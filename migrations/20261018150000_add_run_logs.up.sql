@@ -0,0 +1,12 @@
+-- Output captured while running tasks, e.g. the stderr of process plugins
+CREATE TABLE run_logs (
+    id UUID PRIMARY KEY,
+    run_id UUID NOT NULL REFERENCES job_runs(run_id) ON DELETE CASCADE,
+    task_order INT NOT NULL,
+    line_no INT NOT NULL,
+    stream TEXT NOT NULL,
+    message TEXT NOT NULL,
+    logged_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_run_logs_run_id ON run_logs(run_id, logged_at);
@@ -0,0 +1,3 @@
+-- Command tasks run a program instead of extracting and loading data; their
+-- extractor and loader configs are stored as JSON null
+ALTER TABLE task_definitions ADD COLUMN command_config JSONB;
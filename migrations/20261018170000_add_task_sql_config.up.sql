@@ -0,0 +1,3 @@
+-- SQL tasks run statements against a DuckDB database instead of extracting and
+-- loading data; like command tasks, their extractor and loader configs are JSON null
+ALTER TABLE task_definitions ADD COLUMN sql_config JSONB;
@@ -0,0 +1,7 @@
+-- Secrets referenced from task configs as ${secret:NAME}, encrypted with SECRETS_KEY
+CREATE TABLE secrets (
+    name TEXT PRIMARY KEY,
+    ciphertext TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
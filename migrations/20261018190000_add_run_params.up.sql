@@ -0,0 +1,3 @@
+-- Parameters a job's runs accept, and the parameters each run was triggered with
+ALTER TABLE job_definitions ADD COLUMN params_schema JSONB;
+ALTER TABLE job_runs ADD COLUMN params JSONB;
@@ -0,0 +1,8 @@
+-- Named connections referenced from extractor and loader configs as "connection": "NAME"
+CREATE TABLE connections (
+    name TEXT PRIMARY KEY,
+    connection_type TEXT NOT NULL,
+    options JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
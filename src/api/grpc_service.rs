@@ -1,6 +1,7 @@
 use tonic::{Request, Response, Status};
 use crate::state::db::Db;
-use crate::orchestrator::job_manager::JobManager;
+use crate::orchestrator::job_manager::{JobManager, NewTask};
+use crate::plugins::config::ConfigError;
 use uuid::Uuid;
 
 pub mod proto {
@@ -12,9 +13,57 @@ use proto::{
     GetJobsRequest, GetJobsResponse,
     GetJobRequest, GetJobResponse,
     CreateJobRequest, CreateJobResponse,
-    Job,
+    RunJobRequest, RunJobResponse,
+    Job, TaskConfig,
 };
 
+/// Parses one JSON-encoded config of a task; empty optional configs are `None`.
+fn parse_json(field: String, value: &str, required: bool) -> Result<Option<serde_json::Value>, ConfigError> {
+    if value.is_empty() && !required {
+        return Ok(None);
+    }
+    serde_json::from_str(value)
+        .map(Some)
+        .map_err(|e| ConfigError::new(field, format!("invalid JSON: {}", e)))
+}
+
+fn new_tasks(tasks: Vec<TaskConfig>) -> Result<Vec<NewTask>, ConfigError> {
+    let mut errors = ConfigError::default();
+    let mut parsed = Vec::with_capacity(tasks.len());
+    for (i, task) in tasks.into_iter().enumerate() {
+        let mut field = |name: &str, value: &str, required: bool| match parse_json(format!("tasks[{}].{}", i, name), value, required) {
+            Ok(value) => value,
+            Err(e) => {
+                errors.merge("", Err(e));
+                None
+            }
+        };
+        let command_config = field("command", &task.command, false);
+        let sql_config = field("sql", &task.sql, false);
+        // Command and SQL tasks have no extractor or loader; other tasks need both.
+        let etl = command_config.is_none() && sql_config.is_none();
+        let extractor_config = field("extractor_config", &task.extractor_config, etl);
+        let loader_config = field("loader_config", &task.loader_config, etl);
+        let transform_config = field("transform", &task.transform, false);
+        let checks_config = field("checks", &task.checks, false);
+        let masking_config = field("masking", &task.masking, false);
+        if etl && (extractor_config.is_none() || loader_config.is_none()) {
+            continue;
+        }
+        parsed.push(NewTask {
+            extractor_config: extractor_config.unwrap_or_default(),
+            loader_config: loader_config.unwrap_or_default(),
+            transform_config,
+            checks_config,
+            fail_on_breaking_drift: task.fail_on_breaking_drift,
+            masking_config,
+            command_config,
+            sql_config,
+        });
+    }
+    errors.into_result().map(|_| parsed)
+}
+
 pub struct MyJobService {
     pub db: Db,
 }
@@ -67,15 +116,20 @@ impl JobService for MyJobService {
         let req = request.into_inner();
         
         let job_manager = JobManager::new(self.db.clone());
-        let tasks = vec![]; 
+        let params = parse_json("params".to_string(), &req.params, false).map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let tasks = new_tasks(req.tasks).map_err(|e| Status::invalid_argument(e.to_string()))?;
 
         let job = job_manager.create_job(
             &req.job_name,
             if req.description.is_empty() { None } else { Some(&req.description) },
             &req.schedule,
             req.is_active,
+            params,
             tasks,
-        ).await.map_err(|e| Status::internal(e.to_string()))?;
+        ).await.map_err(|e| match e.downcast::<ConfigError>() {
+            Ok(errors) => Status::invalid_argument(errors.to_string()),
+            Err(e) => Status::internal(e.to_string()),
+        })?;
 
         let proto_job = Job {
             job_id: job.job_id.to_string(),
@@ -87,4 +141,28 @@ impl JobService for MyJobService {
 
         Ok(Response::new(CreateJobResponse { job: Some(proto_job) }))
     }
+
+    async fn run_job(
+        &self,
+        request: Request<RunJobRequest>,
+    ) -> Result<Response<RunJobResponse>, Status> {
+        let req = request.into_inner();
+        let job_uuid = Uuid::parse_str(&req.job_id).map_err(|_| Status::invalid_argument("Invalid UUID format"))?;
+        let params = match parse_json("params".to_string(), &req.params, false).map_err(|e| Status::invalid_argument(e.to_string()))? {
+            Some(serde_json::Value::Object(params)) => params,
+            Some(_) => return Err(Status::invalid_argument(ConfigError::new("params", "expected an object").to_string())),
+            None => serde_json::Map::new(),
+        };
+
+        let run = JobManager::new(self.db.clone())
+            .trigger_run(job_uuid, "manual", &params)
+            .await
+            .map_err(|e| match e.downcast::<ConfigError>() {
+                Ok(errors) => Status::invalid_argument(errors.to_string()),
+                Err(e) => Status::internal(e.to_string()),
+            })?
+            .ok_or_else(|| Status::not_found("Job not found"))?;
+
+        Ok(Response::new(RunJobResponse { run_id: run.run_id.to_string() }))
+    }
 }
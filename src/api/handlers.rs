@@ -16,15 +16,22 @@
 //! This module contains functions that handle incoming HTTP requests, interact with the
 //! `JobManager` and database, and return appropriate HTTP responses.
 
+use crate::auth::Claims;
+use crate::connections;
 use crate::orchestrator::job_manager::{JobManager, NewTask};
+use crate::orchestrator::params::ParamSchema;
+use crate::plugins::config::ConfigError;
+use crate::plugins::registry::registry;
+use crate::secrets::{is_valid_name, SecretStore};
+use crate::templates::{parse_logical_date, RunContext};
 use crate::state::db::Db;
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
 };
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use uuid::Uuid;
 
 use tracing::{info, error}; // Added tracing imports
@@ -37,19 +44,30 @@ pub struct CreateJobRequest {
     pub description: Option<String>,
     pub schedule: String,
     pub is_active: bool,
+    /// The parameters the job's runs accept; see `orchestrator::params`.
+    pub params: Option<Value>,
     pub tasks: Vec<NewTaskRequest>,
 }
 
 #[derive(Deserialize)]
 pub struct NewTaskRequest {
+    #[serde(default)]
     pub extractor_config: Value,
+    #[serde(default)]
     pub loader_config: Value,
+    pub transform: Option<Value>,
+    pub checks: Option<Value>,
+    #[serde(default)]
+    pub fail_on_breaking_drift: bool,
+    pub masking: Option<Value>,
+    pub command: Option<Value>,
+    pub sql: Option<Value>,
 }
 
 pub async fn create_job(
     State(db): State<Db>,
     Json(payload): Json<CreateJobRequest>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, Response> {
     info!("Received request to create job: {}", payload.job_name);
 
     let job_manager = JobManager::new(db);
@@ -59,8 +77,14 @@ pub async fn create_job(
         .map(|t| NewTask {
             extractor_config: t.extractor_config,
             loader_config: t.loader_config,
+            transform_config: t.transform,
+            checks_config: t.checks,
+            fail_on_breaking_drift: t.fail_on_breaking_drift,
+            masking_config: t.masking,
+            command_config: t.command,
+            sql_config: t.sql,
         })
-        .collect();
+        .collect::<Vec<_>>();
 
     let job = job_manager
         .create_job(
@@ -68,12 +92,19 @@ pub async fn create_job(
             payload.description.as_deref(),
             &payload.schedule,
             payload.is_active,
+            payload.params,
             tasks,
         )
         .await
-        .map_err(|e| {
-            error!("Failed to create job {}: {:?}", payload.job_name, e);
-            StatusCode::INTERNAL_SERVER_ERROR
+        .map_err(|e| match e.downcast::<ConfigError>() {
+            Ok(errors) => {
+                error!("Rejected job {}: {}", payload.job_name, errors);
+                (StatusCode::UNPROCESSABLE_ENTITY, Json(errors)).into_response()
+            }
+            Err(e) => {
+                error!("Failed to create job {}: {:?}", payload.job_name, e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
         })?;
 
     info!("Successfully created job: {}", job.job_name);
@@ -107,16 +138,35 @@ pub async fn get_job(State(db): State<Db>, Path(job_id): Path<Uuid>) -> Result<J
     Ok(Json(serde_json::to_value(job).unwrap()))
 }
 
-pub async fn run_job(State(db): State<Db>, Path(job_id): Path<Uuid>) -> Result<StatusCode, StatusCode> {
+#[derive(Deserialize, Default)]
+pub struct RunJobRequest {
+    #[serde(default)]
+    pub params: Map<String, Value>,
+}
+
+pub async fn run_job(
+    State(db): State<Db>,
+    Path(job_id): Path<Uuid>,
+    payload: Option<Json<RunJobRequest>>,
+) -> Result<Json<Value>, Response> {
     info!("Received request to run job: {}", job_id);
-    db.create_job_run(job_id, "queued", "manual")
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let run = JobManager::new(db)
+        .trigger_run(job_id, "manual", &payload.params)
         .await
-        .map_err(|e| {
-            error!("Failed to run job {}: {:?}", job_id, e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .map_err(|e| match e.downcast::<ConfigError>() {
+            Ok(errors) => {
+                error!("Rejected run of job {}: {}", job_id, errors);
+                (StatusCode::UNPROCESSABLE_ENTITY, Json(errors)).into_response()
+            }
+            Err(e) => {
+                error!("Failed to run job {}: {:?}", job_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        })?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
     info!("Successfully queued job run for job: {}", job_id);
-    Ok(StatusCode::OK)
+    Ok(Json(serde_json::to_value(run).unwrap()))
 }
 
 // --- Run Handlers ---
@@ -148,6 +198,286 @@ pub async fn get_run(State(db): State<Db>, Path(run_id): Path<Uuid>) -> Result<J
     Ok(Json(serde_json::to_value(run).unwrap()))
 }
 
+// --- Result Handlers ---
+
+pub async fn get_job_results(State(db): State<Db>, Path(job_id): Path<Uuid>) -> Result<Json<Value>, StatusCode> {
+    info!("Received request to get results for job: {}", job_id);
+    let results = db
+        .get_job_results_for_job(job_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get results for job {}: {:?}", job_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    info!("Successfully retrieved {} results for job: {}", results.len(), job_id);
+    Ok(Json(serde_json::to_value(results).unwrap()))
+}
+
+pub async fn get_run_results(State(db): State<Db>, Path(run_id): Path<Uuid>) -> Result<Json<Value>, StatusCode> {
+    info!("Received request to get results for job run: {}", run_id);
+    let results = db
+        .get_job_results_for_run(run_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get results for job run {}: {:?}", run_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    info!("Successfully retrieved {} results for job run: {}", results.len(), run_id);
+    Ok(Json(serde_json::to_value(results).unwrap()))
+}
+
+pub async fn get_run_checks(State(db): State<Db>, Path(run_id): Path<Uuid>) -> Result<Json<Value>, StatusCode> {
+    info!("Received request to get check results for job run: {}", run_id);
+    let results = db
+        .get_check_results_for_run(run_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get check results for job run {}: {:?}", run_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    info!("Successfully retrieved {} check results for job run: {}", results.len(), run_id);
+    Ok(Json(serde_json::to_value(results).unwrap()))
+}
+
+pub async fn get_run_schema(State(db): State<Db>, Path(run_id): Path<Uuid>) -> Result<Json<Value>, StatusCode> {
+    info!("Received request to get schema snapshots for job run: {}", run_id);
+    let snapshots = db
+        .get_schema_snapshots_for_run(run_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get schema snapshots for job run {}: {:?}", run_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    info!("Successfully retrieved {} schema snapshots for job run: {}", snapshots.len(), run_id);
+    Ok(Json(serde_json::to_value(snapshots).unwrap()))
+}
+
+pub async fn get_run_logs(State(db): State<Db>, Path(run_id): Path<Uuid>) -> Result<Json<Value>, StatusCode> {
+    info!("Received request to get logs for job run: {}", run_id);
+    let lines = db
+        .get_run_logs(run_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get logs for job run {}: {:?}", run_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    info!("Successfully retrieved {} log lines for job run: {}", lines.len(), run_id);
+    Ok(Json(serde_json::to_value(lines).unwrap()))
+}
+
+#[derive(Deserialize, Default)]
+pub struct PreviewRequest {
+    /// `YYYY-MM-DD` or an RFC 3339 timestamp; defaults to now.
+    pub logical_date: Option<String>,
+    #[serde(default)]
+    pub params: Map<String, Value>,
+}
+
+/// Renders the job's task configs for a logical date without running it, merged with the
+/// connections they name. Secret references are left unresolved.
+pub async fn preview_job(
+    State(db): State<Db>,
+    Path(job_id): Path<Uuid>,
+    payload: Option<Json<PreviewRequest>>,
+) -> Result<Json<Value>, Response> {
+    info!("Received request to preview job: {}", job_id);
+    let Json(payload) = payload.unwrap_or_default();
+    let internal_error = |e: anyhow::Error| {
+        error!("Failed to preview job {}: {:?}", job_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    };
+    let unprocessable = |e: anyhow::Error| {
+        error!("Rejected preview of job {}: {:#}", job_id, e);
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(serde_json::json!({ "error": format!("{:#}", e) }))).into_response()
+    };
+
+    let logical_ts = match &payload.logical_date {
+        Some(date) => parse_logical_date(date).map_err(unprocessable)?,
+        None => chrono::Utc::now(),
+    };
+    let Some((job, mut tasks)) = JobManager::new(db.clone()).get_job(job_id).await.map_err(internal_error)? else {
+        info!("Job with ID {} not found.", job_id);
+        return Err(StatusCode::NOT_FOUND.into_response());
+    };
+    let params = ParamSchema::from_stored(job.params_schema.as_ref())
+        .and_then(|schema| schema.apply(&payload.params))
+        .map_err(|e| unprocessable(anyhow::Error::new(e).context("Invalid params")))?;
+    let run_context = RunContext::for_run(&db, &job, Uuid::nil(), logical_ts, params).await.map_err(internal_error)?;
+    let connections = connections::load(&db).await.map_err(internal_error)?;
+    for (i, task) in tasks.iter_mut().enumerate() {
+        let context = |e: ConfigError| unprocessable(anyhow::Error::new(e).context(format!("Task {}", i + 1)));
+        task.extractor_config = connections::apply(&task.extractor_config, &connections).map_err(context)?;
+        task.loader_config = connections::apply(&task.loader_config, &connections).map_err(context)?;
+        for config in task.configs_mut() {
+            *config = run_context.render(config).map_err(|e| unprocessable(e.context(format!("Task {}", i + 1))))?;
+        }
+    }
+    info!("Successfully previewed job: {}", job_id);
+    Ok(Json(serde_json::json!({ "variables": run_context.variables(), "tasks": tasks })))
+}
+
+#[derive(Deserialize)]
+pub struct SetSecretRequest {
+    pub value: String,
+}
+
+/// Lists stored secrets by name. Values are never returned.
+pub async fn get_secrets(_claims: Claims, State(db): State<Db>) -> Result<Json<Value>, StatusCode> {
+    info!("Received request to list secrets.");
+    let secrets = db.get_all_secrets().await.map_err(|e| {
+        error!("Failed to list secrets: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!("Successfully listed {} secrets.", secrets.len());
+    Ok(Json(serde_json::to_value(secrets).unwrap()))
+}
+
+/// Creates or replaces a secret, encrypted with `SECRETS_KEY`.
+pub async fn set_secret(
+    _claims: Claims,
+    State(db): State<Db>,
+    Path(name): Path<String>,
+    Json(payload): Json<SetSecretRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    info!("Received request to set secret: {}", name);
+    if !is_valid_name(&name) {
+        error!("Rejected secret with invalid name: {}", name);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    let store = SecretStore::from_env(db).map_err(|e| {
+        error!("Failed to configure the secret store: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !store.can_store() {
+        error!("Cannot store secret {}: SECRETS_KEY is not set.", name);
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    let secret = store.set(&name, &payload.value).await.map_err(|e| {
+        error!("Failed to set secret {}: {:?}", name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!("Successfully set secret: {}", name);
+    Ok(Json(serde_json::to_value(secret).unwrap()))
+}
+
+pub async fn delete_secret(_claims: Claims, State(db): State<Db>, Path(name): Path<String>) -> Result<StatusCode, StatusCode> {
+    info!("Received request to delete secret: {}", name);
+    let deleted = db.delete_secret(&name).await.map_err(|e| {
+        error!("Failed to delete secret {}: {:?}", name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if deleted {
+        info!("Successfully deleted secret: {}", name);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        info!("Secret {} not found.", name);
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+// --- Connection Handlers ---
+
+#[derive(Deserialize)]
+pub struct SetConnectionRequest {
+    #[serde(rename = "type")]
+    pub connection_type: String,
+    #[serde(default = "empty_options")]
+    pub options: Value,
+}
+
+fn empty_options() -> Value {
+    Value::Object(Map::new())
+}
+
+pub async fn get_connections(_claims: Claims, State(db): State<Db>) -> Result<Json<Value>, StatusCode> {
+    info!("Received request to list connections.");
+    let connections = db.get_all_connections().await.map_err(|e| {
+        error!("Failed to list connections: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    info!("Successfully listed {} connections.", connections.len());
+    Ok(Json(serde_json::to_value(connections).unwrap()))
+}
+
+pub async fn get_connection(_claims: Claims, State(db): State<Db>, Path(name): Path<String>) -> Result<Json<Value>, StatusCode> {
+    info!("Received request to get connection: {}", name);
+    let connection = db.get_connection(&name).await.map_err(|e| {
+        error!("Failed to get connection {}: {:?}", name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let Some(connection) = connection else {
+        info!("Connection {} not found.", name);
+        return Err(StatusCode::NOT_FOUND);
+    };
+    info!("Successfully retrieved connection: {}", name);
+    Ok(Json(serde_json::to_value(connection).unwrap()))
+}
+
+/// Creates or replaces a connection. Jobs that use it pick up the change on their next run.
+pub async fn set_connection(
+    _claims: Claims,
+    State(db): State<Db>,
+    Path(name): Path<String>,
+    Json(payload): Json<SetConnectionRequest>,
+) -> Result<Json<Value>, Response> {
+    info!("Received request to set connection: {}", name);
+    let mut errors = ConfigError::default();
+    if !is_valid_name(&name) {
+        errors.merge("name", Err(ConfigError::new("", "names may only contain letters, digits, '_', '-' and '.'")));
+    }
+    errors.merge("", connections::check_connection(&payload.connection_type, &payload.options).map(|_| ()));
+    errors.into_result().map_err(|e| {
+        error!("Rejected connection {}: {}", name, e);
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(e)).into_response()
+    })?;
+    let connection = db.upsert_connection(&name, &payload.connection_type, &payload.options).await.map_err(|e| {
+        error!("Failed to set connection {}: {:?}", name, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    info!("Successfully set connection: {}", name);
+    Ok(Json(serde_json::to_value(connection).unwrap()))
+}
+
+pub async fn delete_connection(_claims: Claims, State(db): State<Db>, Path(name): Path<String>) -> Result<StatusCode, StatusCode> {
+    info!("Received request to delete connection: {}", name);
+    let deleted = db.delete_connection(&name).await.map_err(|e| {
+        error!("Failed to delete connection {}: {:?}", name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if deleted {
+        info!("Successfully deleted connection: {}", name);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        info!("Connection {} not found.", name);
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Checks whether a connection is reachable. An unreachable connection is still a `200 OK`,
+/// with `ok` false and the reason in `message`.
+pub async fn test_connection(_claims: Claims, State(db): State<Db>, Path(name): Path<String>) -> Result<Json<Value>, StatusCode> {
+    info!("Received request to test connection: {}", name);
+    let internal_error = |e: anyhow::Error| {
+        error!("Failed to test connection {}: {:?}", name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let Some(connection) = db.get_connection(&name).await.map_err(internal_error)? else {
+        info!("Connection {} not found.", name);
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let secrets = SecretStore::from_env(db).map_err(internal_error)?;
+    let outcome = connections::test(&connection, &secrets).await;
+    info!("Tested connection {}: ok = {}, {}", name, outcome.ok, outcome.message);
+    Ok(Json(serde_json::to_value(outcome).unwrap()))
+}
+
+pub async fn list_plugins() -> Result<Json<Value>, StatusCode> {
+    info!("Received request to list plugins.");
+    let plugins = registry().list();
+    info!("Successfully listed {} plugins.", plugins.len());
+    Ok(Json(serde_json::to_value(plugins).unwrap()))
+}
+
 pub async fn health_check() -> Result<StatusCode, StatusCode> {
     tracing::info!("Health check requested.");
     Ok(StatusCode::OK)
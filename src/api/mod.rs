@@ -10,7 +10,7 @@ pub mod grpc_service;
 
 use crate::state::db::Db;
 use axum::{
-    routing::{get, post},
+    routing::{get, post, put},
     Router,
 };
 
@@ -20,8 +20,23 @@ pub fn app(db: Db) -> Router {
         .route("/jobs", post(handlers::create_job).get(handlers::get_jobs))
         .route("/jobs/{job_id}", get(handlers::get_job))
         .route("/jobs/{job_id}/run", post(handlers::run_job))
+        .route("/jobs/{job_id}/preview", post(handlers::preview_job))
+        .route("/jobs/{job_id}/results", get(handlers::get_job_results))
         .route("/runs", get(handlers::get_runs))
         .route("/runs/{run_id}", get(handlers::get_run))
+        .route("/runs/{run_id}/results", get(handlers::get_run_results))
+        .route("/runs/{run_id}/checks", get(handlers::get_run_checks))
+        .route("/runs/{run_id}/schema", get(handlers::get_run_schema))
+        .route("/runs/{run_id}/logs", get(handlers::get_run_logs))
+        .route("/secrets", get(handlers::get_secrets))
+        .route("/secrets/{name}", put(handlers::set_secret).delete(handlers::delete_secret))
+        .route("/connections", get(handlers::get_connections))
+        .route(
+            "/connections/{name}",
+            get(handlers::get_connection).put(handlers::set_connection).delete(handlers::delete_connection),
+        )
+        .route("/connections/{name}/test", post(handlers::test_connection))
+        .route("/plugins", get(handlers::list_plugins))
         .route("/auth/register", post(auth::register))
         .route("/auth/login", post(auth::login))
         .with_state(db)
@@ -71,10 +71,7 @@ mod tests {
     use jsonwebtoken::{encode, EncodingKey, Header};
 
     #[tokio::test]
-    #[ignore]
     async fn test_claims_validation() {
-        // This test requires a global crypto provider to be installed for rustls/jsonwebtoken
-        // let _ = rustls::crypto::ring::default_provider().install_default();
         let claims = Claims {
             sub: "user-123".to_string(),
             exp: (chrono::Utc::now() + chrono::Duration::hours(1)).timestamp() as usize,
@@ -0,0 +1,304 @@
+//! Named connections, referenced from extractor and loader configs.
+//!
+//! Rather than repeating a database path or an API's base URL in every task, a connection is
+//! stored once, through the `/connections` API, and configs name it:
+//! `{"type": "duckdb", "connection": "warehouse", "table_name": "users"}`. The connection's
+//! `options` are the defaults of the config, which may override any of them. Connections are
+//! looked up when a job is created, to validate its configs, and again each time a task runs,
+//! so changing a connection applies to every job that uses it. Credentials belong in the
+//! options as secret references (`${secret:NAME}`), which are resolved after the merge.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::plugins::config::ConfigError;
+use crate::plugins::RunLog;
+use crate::secrets::{check_references, SecretStore};
+use crate::state::db::{ConnectionDefinition, Db};
+
+/// Config key naming the connection a config uses.
+pub const CONNECTION_KEY: &str = "connection";
+
+/// How long a connection test may take.
+const TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionType {
+    Postgres,
+    Duckdb,
+    Sqlite,
+    Http,
+}
+
+impl ConnectionType {
+    const ALL: [ConnectionType; 4] = [ConnectionType::Postgres, ConnectionType::Duckdb, ConnectionType::Sqlite, ConnectionType::Http];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConnectionType::Postgres => "postgres",
+            ConnectionType::Duckdb => "duckdb",
+            ConnectionType::Sqlite => "sqlite",
+            ConnectionType::Http => "http",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == name)
+    }
+
+    /// The extractor and loader types whose configs may use a connection of the type.
+    pub fn config_types(self) -> &'static [&'static str] {
+        match self {
+            ConnectionType::Postgres => &["postgres"],
+            ConnectionType::Duckdb => &["duckdb"],
+            ConnectionType::Sqlite => &["sqlite"],
+            ConnectionType::Http => &["api", "http"],
+        }
+    }
+
+    /// The option locating the database or service, which every connection of the type sets.
+    pub fn address_field(self) -> &'static str {
+        match self {
+            ConnectionType::Postgres => "connection_url",
+            ConnectionType::Duckdb | ConnectionType::Sqlite => "db_path",
+            ConnectionType::Http => "url",
+        }
+    }
+}
+
+/// Connections by name.
+pub type Connections = BTreeMap<String, ConnectionDefinition>;
+
+/// All stored connections.
+pub async fn load(db: &Db) -> Result<Connections> {
+    let connections = db.get_all_connections().await.context("Failed to load connections")?;
+    Ok(connections.into_iter().map(|c| (c.name.clone(), c)).collect())
+}
+
+/// Checks a connection's type and options before it is stored.
+pub fn check_connection(connection_type: &str, options: &Value) -> Result<ConnectionType, ConfigError> {
+    let Some(parsed) = ConnectionType::parse(connection_type) else {
+        let known: Vec<&str> = ConnectionType::ALL.iter().map(|t| t.as_str()).collect();
+        return Err(ConfigError::new(
+            "type",
+            format!("unknown connection type `{}`, expected one of: {}", connection_type, known.join(", ")),
+        ));
+    };
+    let Some(fields) = options.as_object() else {
+        return Err(ConfigError::new("options", "options must be an object"));
+    };
+    let mut errors = ConfigError::default();
+    if !fields.get(parsed.address_field()).is_some_and(Value::is_string) {
+        errors.merge("options", Err(ConfigError::new(parsed.address_field(), format!("a {} connection needs a string {}", parsed.as_str(), parsed.address_field()))));
+    }
+    for key in ["type", CONNECTION_KEY] {
+        if fields.contains_key(key) {
+            errors.merge("options", Err(ConfigError::new(key, "set by the config that uses the connection")));
+        }
+    }
+    errors.merge("options", check_references(options));
+    errors.into_result().map(|_| parsed)
+}
+
+/// Merges the connection a config names into it, with the config's own fields taking
+/// precedence. The config's `type` must be one the connection's type serves. A list of loader
+/// configs is merged entry by entry; configs without a `connection` are returned unchanged.
+pub fn apply(config: &Value, connections: &Connections) -> Result<Value, ConfigError> {
+    match config {
+        Value::Array(items) => {
+            let mut errors = ConfigError::default();
+            let mut merged = Vec::with_capacity(items.len());
+            for (i, item) in items.iter().enumerate() {
+                match apply(item, connections) {
+                    Ok(item) => merged.push(item),
+                    Err(e) => errors.merge(&format!("[{}]", i), Err(e)),
+                }
+            }
+            errors.into_result().map(|_| Value::Array(merged))
+        }
+        Value::Object(fields) if fields.contains_key(CONNECTION_KEY) => {
+            let name = fields[CONNECTION_KEY]
+                .as_str()
+                .ok_or_else(|| ConfigError::new(CONNECTION_KEY, "expected the name of a connection"))?;
+            let connection = connections
+                .get(name)
+                .ok_or_else(|| ConfigError::new(CONNECTION_KEY, format!("unknown connection '{}'", name)))?;
+            let config_type = fields.get("type").and_then(Value::as_str);
+            if let (Some(config_type), Some(connection_type)) = (config_type, ConnectionType::parse(&connection.connection_type)) {
+                if !connection_type.config_types().contains(&config_type) {
+                    return Err(ConfigError::new(
+                        CONNECTION_KEY,
+                        format!("connection '{}' is a {} connection, which a {} config can't use", name, connection_type.as_str(), config_type),
+                    ));
+                }
+            }
+            let mut merged: Map<String, Value> = connection.options.as_object().cloned().unwrap_or_default();
+            merged.extend(fields.iter().filter(|(key, _)| *key != CONNECTION_KEY).map(|(key, value)| (key.clone(), value.clone())));
+            Ok(Value::Object(merged))
+        }
+        other => Ok(other.clone()),
+    }
+}
+
+/// Outcome of testing a connection.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionTest {
+    pub ok: bool,
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+/// Checks a connection is reachable: that a database opens (or, for a file that doesn't
+/// exist yet, that the directory it would be created in does) or that a service answers HTTP
+/// requests. Secret values are redacted from the outcome.
+pub async fn test(connection: &ConnectionDefinition, secrets: &SecretStore) -> ConnectionTest {
+    let started = Instant::now();
+    let run_log = RunLog::default();
+    let result = match tokio::time::timeout(TEST_TIMEOUT, reach(connection, secrets, &run_log)).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!("No answer within {}s", TEST_TIMEOUT.as_secs())),
+    };
+    let (ok, message) = match result {
+        Ok(message) => (true, message),
+        Err(e) => (false, format!("{:#}", e)),
+    };
+    ConnectionTest { ok, message: run_log.redact(&message), duration_ms: started.elapsed().as_millis() as u64 }
+}
+
+async fn reach(connection: &ConnectionDefinition, secrets: &SecretStore, run_log: &RunLog) -> Result<String> {
+    let connection_type = ConnectionType::parse(&connection.connection_type)
+        .with_context(|| format!("Unknown connection type '{}'", connection.connection_type))?;
+    let options = secrets.resolve(&connection.options, run_log).await?;
+    let address = options[connection_type.address_field()]
+        .as_str()
+        .with_context(|| format!("Connection '{}' has no {}", connection.name, connection_type.address_field()))?
+        .to_string();
+    match connection_type {
+        ConnectionType::Postgres => {
+            use sqlx::Connection;
+            let mut conn = sqlx::PgConnection::connect(&address).await.context("Failed to connect")?;
+            conn.ping().await.context("Failed to ping the database")?;
+            conn.close().await?;
+            Ok("Connected".to_string())
+        }
+        ConnectionType::Sqlite => {
+            if let Some(message) = not_created_yet(&address)? {
+                return Ok(message);
+            }
+            use sqlx::Connection;
+            let options = sqlx::sqlite::SqliteConnectOptions::new().filename(&address).read_only(true);
+            let mut conn = sqlx::SqliteConnection::connect_with(&options).await.context("Failed to open the database")?;
+            conn.ping().await.context("Failed to query the database")?;
+            conn.close().await?;
+            Ok("Connected".to_string())
+        }
+        ConnectionType::Duckdb => {
+            if let Some(message) = not_created_yet(&address)? {
+                return Ok(message);
+            }
+            tokio::task::spawn_blocking(move || -> Result<String> {
+                let config = duckdb::Config::default().access_mode(duckdb::AccessMode::ReadOnly)?;
+                let conn = duckdb::Connection::open_with_flags(&address, config).context("Failed to open the database")?;
+                conn.execute_batch("SELECT 1").context("Failed to query the database")?;
+                Ok("Connected".to_string())
+            })
+            .await
+            .context("DuckDB connection test panicked")?
+        }
+        ConnectionType::Http => {
+            let response = reqwest::Client::new().get(&address).send().await.context("Request failed")?;
+            Ok(format!("Reachable (HTTP {})", response.status()))
+        }
+    }
+}
+
+/// For a database file that doesn't exist yet, whether the loader could create it.
+fn not_created_yet(path: &str) -> Result<Option<String>> {
+    let path = Path::new(path);
+    if path.exists() {
+        return Ok(None);
+    }
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    if !dir.is_dir() {
+        anyhow::bail!("Neither '{}' nor its directory '{}' exists", path.display(), dir.display());
+    }
+    Ok(Some(format!("'{}' doesn't exist yet; it will be created in '{}'", path.display(), dir.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn connection(name: &str, connection_type: &str, options: Value) -> ConnectionDefinition {
+        ConnectionDefinition {
+            name: name.to_string(),
+            connection_type: connection_type.to_string(),
+            options,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_merges_connections_into_configs() {
+        let connections = Connections::from([(
+            "warehouse".to_string(),
+            connection("warehouse", "duckdb", json!({ "db_path": "warehouse.duckdb", "mode": "append" })),
+        )]);
+        let config = json!([
+            { "type": "duckdb", "connection": "warehouse", "table_name": "users", "mode": "replace" },
+            { "type": "csv", "path": "users.csv" }
+        ]);
+        assert_eq!(
+            apply(&config, &connections).unwrap(),
+            json!([
+                { "type": "duckdb", "db_path": "warehouse.duckdb", "table_name": "users", "mode": "replace" },
+                { "type": "csv", "path": "users.csv" }
+            ])
+        );
+
+        let errors = apply(&json!([{ "connection": "lake" }, { "connection": 1 }]), &connections).unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["[0].connection", "[1].connection"]);
+        assert_eq!(errors.errors[0].message, "unknown connection 'lake'");
+
+        let errors = apply(&json!({ "type": "postgres", "connection": "warehouse", "table_name": "users" }), &connections).unwrap_err();
+        assert_eq!(errors.errors[0].field, "connection");
+        assert_eq!(errors.errors[0].message, "connection 'warehouse' is a duckdb connection, which a postgres config can't use");
+    }
+
+    #[test]
+    fn test_checks_connection_options() {
+        assert_eq!(check_connection("http", &json!({ "url": "https://api.example.com" })).unwrap(), ConnectionType::Http);
+        assert_eq!(check_connection("oracle", &json!({})).unwrap_err().errors[0].field, "type");
+        let errors = check_connection("postgres", &json!({ "type": "postgres", "password": "${secret:bad name}" })).unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["options.connection_url", "options.type", "options.password"]);
+    }
+
+    #[tokio::test]
+    async fn test_reports_whether_a_database_is_reachable() {
+        let db = Db::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let secrets = SecretStore::from_env(db).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("warehouse.duckdb");
+
+        let outcome = test(&connection("warehouse", "duckdb", json!({ "db_path": path })), &secrets).await;
+        assert!(outcome.ok && outcome.message.contains("doesn't exist yet"), "{}", outcome.message);
+        duckdb::Connection::open(&path).unwrap().execute_batch("CREATE TABLE t (id INTEGER)").unwrap();
+        let outcome = test(&connection("warehouse", "duckdb", json!({ "db_path": path })), &secrets).await;
+        assert!(outcome.ok, "{}", outcome.message);
+
+        let missing = dir.path().join("missing/app.db");
+        let outcome = test(&connection("app", "sqlite", json!({ "db_path": missing })), &secrets).await;
+        assert!(!outcome.ok);
+        assert!(outcome.message.starts_with("Neither"), "{}", outcome.message);
+    }
+}
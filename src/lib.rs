@@ -34,6 +34,9 @@ use std::net::SocketAddr;
 pub mod api;
 pub mod orchestrator;
 pub mod plugins;
+pub mod connections;
+pub mod secrets;
+pub mod templates;
 pub mod state;
 pub mod auth;
 pub mod worker;
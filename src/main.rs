@@ -11,9 +11,12 @@
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use orc_rust_ator::orchestrator::job_manager::JobManager;
 use orc_rust_ator::state::db::Db;
 use orc_rust_ator::utils::seeder;
+use serde_json::{Map, Value};
 use std::env;
+use uuid::Uuid;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -29,6 +32,13 @@ enum Commands {
         #[arg(short, long, default_value = "jobs.yaml")]
         file: String,
     },
+    /// Queue a manual run of a job
+    Run {
+        job_id: Uuid,
+        /// Run parameters, as a JSON object
+        #[arg(short, long, default_value = "{}")]
+        params: String,
+    },
     /// Start the application server (default)
     Start,
 }
@@ -52,6 +62,18 @@ async fn main() -> Result<()> {
             seeder::seed_jobs(&db, file).await?;
             println!("Init completed successfully.");
         }
+        Some(Commands::Run { job_id, params }) => {
+            let params: Map<String, Value> = serde_json::from_str(params).context("--params must be a JSON object")?;
+            let database_url = env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
+            let db = Db::new(&database_url).await?;
+            db.migrate().await?;
+
+            let run = JobManager::new(db)
+                .trigger_run(*job_id, "manual", &params)
+                .await?
+                .with_context(|| format!("Job {} not found", job_id))?;
+            println!("Queued run {} of job {}.", run.run_id, job_id);
+        }
         Some(Commands::Start) | None => {
             orc_rust_ator::run_app().await?;
         }
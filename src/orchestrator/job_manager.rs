@@ -15,9 +15,19 @@
 //! This module provides the `JobManager` struct, which offers CRUD (Create, Read, Update, Delete)
 //! functionality for job definitions and their tasks, interacting directly with the database.
 
-use crate::state::db::{Db, JobDefinition, TaskDefinition};
+use crate::plugins::checks::parse_checks;
+use crate::plugins::command::parse_command;
+use crate::plugins::sql_task::parse_sql_task;
+use crate::connections::{self, Connections};
+use crate::secrets::check_references;
+use crate::templates::check_templates;
+use crate::plugins::masking::parse_masking;
+use crate::plugins::config::ConfigError;
+use crate::plugins::registry::registry;
+use crate::orchestrator::params::ParamSchema;
+use crate::state::db::{Db, JobDefinition, JobRun, NewTaskDefinition, TaskDefinition};
 use anyhow::{Context, Result};
-use serde_json::Value;
+use serde_json::{Map, Value};
 use uuid::Uuid;
 
 use tracing::info;
@@ -32,20 +42,134 @@ impl JobManager {
         Self { db }
     }
 
+    /// Checks every task config against its plugin before anything is stored. All problems
+    /// are reported together, each on the path of the offending field (e.g.
+    /// `tasks[0].loader_config[1].table_name`). A task either extracts and loads data or, with
+    /// a `command`, runs a program, or, with `sql`, runs statements against a DuckDB database.
+    /// Secret references (`${secret:NAME}`) and template expressions (`{{ ds }}`) must be
+    /// well-formed; their values are only known when the task runs, and templates may only use
+    /// the parameters in `params`. Extractor and loader configs naming a `connection` are
+    /// checked merged with it.
+    pub fn validate_tasks(tasks: &[NewTask], params: &ParamSchema, connections: &Connections) -> Result<(), ConfigError> {
+        let registry = registry();
+        let mut errors = ConfigError::default();
+        for (i, task) in tasks.iter().enumerate() {
+            let prefix = format!("tasks[{}]", i);
+            let configs = [
+                ("extractor_config", Some(&task.extractor_config)),
+                ("loader_config", Some(&task.loader_config)),
+                ("transform", task.transform_config.as_ref()),
+                ("checks", task.checks_config.as_ref()),
+                ("masking", task.masking_config.as_ref()),
+                ("command", task.command_config.as_ref()),
+                ("sql", task.sql_config.as_ref()),
+            ];
+            for (field, config) in configs {
+                if let Some(config) = config {
+                    errors.merge(&format!("{}.{}", prefix, field), check_references(config));
+                    errors.merge(&format!("{}.{}", prefix, field), check_templates(config, params));
+                }
+            }
+            let standalone: Vec<(&str, &Value)> = [("command", &task.command_config), ("sql", &task.sql_config)]
+                .into_iter()
+                .filter_map(|(kind, config)| config.as_ref().map(|c| (kind, c)))
+                .collect();
+            if let Some(&(kind, _)) = standalone.first() {
+                for &(kind, config) in &standalone {
+                    let parsed = match kind {
+                        "command" => parse_command(config).map(|_| ()),
+                        _ => parse_sql_task(config).map(|_| ()),
+                    };
+                    errors.merge(&format!("{}.{}", prefix, kind), parsed);
+                }
+                if let Some(&(other, _)) = standalone.get(1) {
+                    errors.merge(&format!("{}.{}", prefix, other), Err(ConfigError::new("", format!("not allowed on a {} task", kind))));
+                }
+                let etl_fields = [
+                    ("extractor_config", !task.extractor_config.is_null()),
+                    ("loader_config", !task.loader_config.is_null()),
+                    ("transform", task.transform_config.is_some()),
+                    ("checks", task.checks_config.is_some()),
+                    ("fail_on_breaking_drift", task.fail_on_breaking_drift),
+                    ("masking", task.masking_config.is_some()),
+                ];
+                for (field, _) in etl_fields.into_iter().filter(|(_, present)| *present) {
+                    errors.merge(&format!("{}.{}", prefix, field), Err(ConfigError::new("", format!("not allowed on a {} task", kind))));
+                }
+                continue;
+            }
+            if task.extractor_config.is_null() && task.loader_config.is_null() {
+                errors.merge(&prefix, Err(ConfigError::new("", "a task needs extractor_config and loader_config, a command or sql")));
+                continue;
+            }
+            match connections::apply(&task.extractor_config, connections) {
+                Ok(extractor) => errors.merge(&format!("{}.extractor_config", prefix), registry.validate_extractor(&extractor)),
+                Err(e) => errors.merge(&format!("{}.extractor_config", prefix), Err(e)),
+            }
+            let loader_config = connections::apply(&task.loader_config, connections);
+            match &loader_config {
+                Err(e) => errors.merge(&format!("{}.loader_config", prefix), Err(e.clone())),
+                Ok(Value::Array(loaders)) if loaders.is_empty() => {
+                    errors.merge(&format!("{}.loader_config", prefix), Err(ConfigError::new("", "loader_config list is empty")));
+                }
+                Ok(Value::Array(loaders)) => {
+                    for (j, loader) in loaders.iter().enumerate() {
+                        errors.merge(&format!("{}.loader_config[{}]", prefix, j), registry.validate_loader(loader));
+                    }
+                }
+                Ok(loader) => errors.merge(&format!("{}.loader_config", prefix), registry.validate_loader(loader)),
+            }
+            if let Some(transform) = &task.transform_config {
+                match transform.as_array() {
+                    Some(steps) => {
+                        for (j, step) in steps.iter().enumerate() {
+                            errors.merge(&format!("{}.transform[{}]", prefix, j), registry.validate_transform(step));
+                        }
+                    }
+                    None => errors.merge(&format!("{}.transform", prefix), Err(ConfigError::new("", "transform must be a list of steps"))),
+                }
+            }
+            if let Some(checks) = &task.checks_config {
+                let result = parse_checks(checks).map(|_| ()).map_err(|e| ConfigError::new("", format!("{:#}", e)));
+                errors.merge(&format!("{}.checks", prefix), result);
+            }
+            if let Some(masking) = &task.masking_config {
+                let result = parse_masking(masking).map(|_| ()).map_err(|e| ConfigError::new("", format!("{:#}", e)));
+                errors.merge(&format!("{}.masking", prefix), result);
+            }
+        }
+        errors.into_result()
+    }
+
+    /// Checks a job's parameter schema (reported under `params`), then its tasks.
+    pub fn validate_job(params_schema: Option<&Value>, tasks: &[NewTask], connections: &Connections) -> Result<(), ConfigError> {
+        let params = ParamSchema::from_stored(params_schema).map_err(|e| {
+            let mut errors = ConfigError::default();
+            errors.merge("params", Err(e));
+            errors
+        })?;
+        Self::validate_tasks(tasks, &params, connections)
+    }
+
     /// Creates a new job definition and its associated tasks in the database.
     /// This is an atomic operation within the database (implicitly or explicitly handled by Db).
+    /// Tasks are validated first, so an invalid task leaves nothing behind; the error is then a
+    /// `ConfigError`.
     pub async fn create_job(
         &self,
         job_name: &str,
         description: Option<&str>,
         schedule: &str,
         is_active: bool,
+        params_schema: Option<Value>,
         tasks: Vec<NewTask>,
     ) -> Result<JobDefinition> {
         info!("JobManager: Creating job definition for '{}'", job_name);
+        let connections = connections::load(&self.db).await?;
+        Self::validate_job(params_schema.as_ref(), &tasks, &connections)?;
         let job = self
             .db
-            .create_job_definition(job_name, description, schedule, is_active)
+            .create_job_definition(job_name, description, schedule, is_active, params_schema.as_ref())
             .await
             .context(format!("Failed to create job definition for '{}'", job_name))?;
 
@@ -54,12 +178,18 @@ impl JobManager {
         for (i, task) in tasks.into_iter().enumerate() {
             info!("JobManager: Creating task {} for job '{}'", i + 1, job.job_id);
             self.db
-                .create_task_definition(
-                    job.job_id,
-                    i as i32 + 1,
-                    &task.extractor_config,
-                    &task.loader_config,
-                )
+                .create_task_definition(&NewTaskDefinition {
+                    job_id: job.job_id,
+                    task_order: i as i32 + 1,
+                    extractor_config: task.extractor_config,
+                    loader_config: task.loader_config,
+                    transform_config: task.transform_config,
+                    checks_config: task.checks_config,
+                    fail_on_breaking_drift: task.fail_on_breaking_drift,
+                    masking_config: task.masking_config,
+                    command_config: task.command_config,
+                    sql_config: task.sql_config,
+                })
                 .await
                 .context(format!("Failed to create task {} for job '{}'", i + 1, job.job_id))?;
             info!("JobManager: Task {} for job '{}' created.", i + 1, job.job_id);
@@ -69,6 +199,30 @@ impl JobManager {
         Ok(job)
     }
 
+    /// Queues a run of a job. `params` are checked against the job's parameter schema and
+    /// completed with defaults; problems are a `ConfigError` with fields under `params`.
+    /// Returns `None` if the job doesn't exist.
+    pub async fn trigger_run(&self, job_id: Uuid, triggered_by: &str, params: &Map<String, Value>) -> Result<Option<JobRun>> {
+        info!("JobManager: Triggering run of job '{}' ({})", job_id, triggered_by);
+        let Some(job) = self.db.get_job_definition(job_id).await.context(format!("Failed to get job definition for ID: {}", job_id))? else {
+            return Ok(None);
+        };
+        let params = ParamSchema::from_stored(job.params_schema.as_ref())
+            .and_then(|schema| schema.apply(params))
+            .map_err(|e| {
+                let mut errors = ConfigError::default();
+                errors.merge("params", Err(e));
+                errors
+            })?;
+        let run = self
+            .db
+            .create_job_run(job_id, "queued", triggered_by, Some(&Value::Object(params)))
+            .await
+            .context(format!("Failed to queue a run of job '{}'", job_id))?;
+        info!("JobManager: Queued run '{}' of job '{}'", run.run_id, job_id);
+        Ok(Some(run))
+    }
+
     /// Retrieves a job definition and all its associated tasks by job ID.
     pub async fn get_job(&self, job_id: Uuid) -> Result<Option<(JobDefinition, Vec<TaskDefinition>)>> {
         info!("JobManager: Attempting to retrieve job with ID: {}", job_id);
@@ -87,6 +241,15 @@ impl JobManager {
 pub struct NewTask {
     pub extractor_config: Value,
     pub loader_config: Value,
+    pub transform_config: Option<Value>,
+    pub checks_config: Option<Value>,
+    /// Fails the task on removed or retyped columns instead of loading the data.
+    pub fail_on_breaking_drift: bool,
+    pub masking_config: Option<Value>,
+    /// Makes this a command task; its extractor and loader configs are then `Value::Null`.
+    pub command_config: Option<Value>,
+    /// Makes this a SQL task, with the same null extractor and loader configs.
+    pub sql_config: Option<Value>,
 }
 
 #[cfg(test)]
@@ -110,10 +273,16 @@ mod tests {
         let tasks = vec![NewTask {
             extractor_config: json!({ "type": "api", "url": "https://example.com" }),
             loader_config: json!({ "type": "duckdb", "db_path": "test.db", "table_name": "test" }),
+            transform_config: Some(json!([{ "op": "limit", "n": 10 }])),
+            checks_config: None,
+            fail_on_breaking_drift: true,
+            masking_config: None,
+            command_config: None,
+            sql_config: None,
         }];
 
         let job = job_manager
-            .create_job("Test Job", Some("Test Description"), "@manual", true, tasks)
+            .create_job("Test Job", Some("Test Description"), "@manual", true, None, tasks)
             .await
             .unwrap();
 
@@ -122,5 +291,135 @@ mod tests {
         assert_eq!(job.job_id, retrieved_job.job_id);
         assert_eq!(job.job_name, retrieved_job.job_name);
         assert_eq!(retrieved_tasks.len(), 1);
+        assert_eq!(retrieved_tasks[0].transform_config, Some(json!([{ "op": "limit", "n": 10 }])));
+        assert!(retrieved_tasks[0].fail_on_breaking_drift);
+    }
+
+    #[tokio::test]
+    async fn test_create_job_rejects_invalid_transform() {
+        let db = setup().await;
+        let job_manager = JobManager::new(db.clone());
+
+        let tasks = vec![NewTask {
+            extractor_config: json!({ "type": "csv", "path": "data.csv" }),
+            loader_config: json!({ "type": "duckdb", "db_path": "test.db", "table_name": "test" }),
+            transform_config: Some(json!([{ "op": "cast", "columns": { "id": "money" } }])),
+            checks_config: None,
+            fail_on_breaking_drift: false,
+            masking_config: None,
+            command_config: None,
+            sql_config: None,
+        }];
+
+        let err = job_manager.create_job("Bad Job", None, "@manual", true, None, tasks).await.unwrap_err();
+        // Callers map the validation errors to a client error by downcasting them.
+        assert_eq!(err.downcast_ref::<ConfigError>().unwrap().errors[0].field, "tasks[0].transform[0].columns");
+        assert!(db.get_all_job_definitions().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate_tasks_reports_each_invalid_field() {
+        let tasks = vec![NewTask {
+            extractor_config: json!({ "type": "csv", "path": "data.csv", "fail_on_breaking_drift": true }),
+            loader_config: json!([
+                { "type": "warehouse", "target": "duckdb" },
+                { "type": "duckdb", "db_path": "test.db", "required": false },
+                { "type": "sqlite", "db_path": "out.db", "table_name": "t", "mode": "upsert" },
+                { "type": "postgres", "connection_url": "postgres://db", "table_name": "t", "mode": "merge" }
+            ]),
+            transform_config: Some(json!([{ "op": "limit", "n": 1 }, { "op": "filter", "expression": "a > 1" }])),
+            checks_config: None,
+            fail_on_breaking_drift: false,
+            masking_config: None,
+            command_config: None,
+            sql_config: None,
+        }];
+
+        let errors = JobManager::validate_tasks(&tasks, &ParamSchema::default(), &Connections::new()).unwrap_err();
+
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                // A task-level option, not an extractor one.
+                "tasks[0].extractor_config.fail_on_breaking_drift",
+                "tasks[0].loader_config[0].type",
+                "tasks[0].loader_config[1]",
+                "tasks[0].loader_config[2].key_columns",
+                "tasks[0].loader_config[3].mode",
+                "tasks[0].transform[1]",
+            ]
+        );
+        assert!(errors.errors[2].message.contains("missing field `table_name`"));
+    }
+
+    #[tokio::test]
+    async fn test_trigger_run_checks_params_against_the_schema() {
+        let db = setup().await;
+        let job_manager = JobManager::new(db.clone());
+        let task = |path: &str| NewTask {
+            extractor_config: json!({ "type": "csv", "path": path }),
+            loader_config: json!({ "type": "duckdb", "db_path": "test.db", "table_name": "test" }),
+            transform_config: None,
+            checks_config: None,
+            fail_on_breaking_drift: false,
+            masking_config: None,
+            command_config: None,
+            sql_config: None,
+        };
+        let schema = json!({ "file": { "type": "string", "required": true }, "limit": { "type": "integer", "default": 10 } });
+
+        let errors = JobManager::validate_job(Some(&json!({ "file": { "type": "path" } })), &[task("x.csv")], &Connections::new()).unwrap_err();
+        assert_eq!(errors.errors[0].field, "params.file.type");
+        let errors = JobManager::validate_job(Some(&schema), &[task("{{ params.dir }}/x.csv")], &Connections::new()).unwrap_err();
+        assert_eq!(errors.errors[0].field, "tasks[0].extractor_config.path");
+
+        let job = job_manager.create_job("Backfill", None, "@manual", true, Some(schema), vec![task("{{ params.file }}")]).await.unwrap();
+        let params = json!({ "file": "2026-10-01.csv" });
+        let run = job_manager.trigger_run(job.job_id, "manual", params.as_object().unwrap()).await.unwrap().unwrap();
+        assert_eq!(run.params, Some(json!({ "file": "2026-10-01.csv", "limit": 10 })));
+        assert_eq!(db.get_job_run(run.run_id).await.unwrap().unwrap().params, run.params);
+
+        let err = job_manager.trigger_run(job.job_id, "manual", &Map::new()).await.unwrap_err();
+        let errors = err.downcast::<ConfigError>().unwrap();
+        assert_eq!((errors.errors[0].field.as_str(), errors.errors[0].message.as_str()), ("params.file", "required parameter is missing"));
+        assert!(job_manager.trigger_run(Uuid::new_v4(), "manual", &Map::new()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_command_tasks_share_the_task_list() {
+        let db = setup().await;
+        let job_manager = JobManager::new(db);
+        let etl = || NewTask {
+            extractor_config: json!({ "type": "csv", "path": "data.csv" }),
+            loader_config: json!({ "type": "duckdb", "db_path": "test.db", "table_name": "test" }),
+            transform_config: None,
+            checks_config: None,
+            fail_on_breaking_drift: false,
+            masking_config: None,
+            command_config: None,
+            sql_config: None,
+        };
+        let command = |extractor_config: Value| NewTask {
+            extractor_config,
+            loader_config: Value::Null,
+            command_config: Some(json!({ "command": "dbt", "args": ["run"] })),
+            ..etl()
+        };
+
+        let errors = JobManager::validate_tasks(&[command(json!({ "type": "csv", "path": "x.csv" }))], &ParamSchema::default(), &Connections::new()).unwrap_err();
+        assert_eq!(errors.errors[0].field, "tasks[0].extractor_config");
+        let both = NewTask { sql_config: Some(json!({ "db_path": "test.db", "statements": ["SELECT 1"] })), ..command(Value::Null) };
+        let errors = JobManager::validate_tasks(&[both], &ParamSchema::default(), &Connections::new()).unwrap_err();
+        assert_eq!((errors.errors[0].field.as_str(), errors.errors[0].message.as_str()), ("tasks[0].sql", "not allowed on a command task"));
+        let drift_checked = NewTask { fail_on_breaking_drift: true, ..command(Value::Null) };
+        let errors = JobManager::validate_tasks(&[drift_checked], &ParamSchema::default(), &Connections::new()).unwrap_err();
+        assert_eq!(errors.errors[0].field, "tasks[0].fail_on_breaking_drift");
+
+        let job = job_manager.create_job("dbt after load", None, "@manual", true, None, vec![etl(), command(Value::Null)]).await.unwrap();
+        let (_, tasks) = job_manager.get_job(job.job_id).await.unwrap().unwrap();
+        assert_eq!(tasks[1].task_order, 2);
+        assert_eq!(tasks[1].extractor_config, Value::Null);
+        assert_eq!(tasks[1].command_config.as_ref().unwrap()["args"], json!(["run"]));
     }
 }
@@ -6,5 +6,6 @@
 //! `JobManager`, `Scheduler`, and `WorkerManager`.
 
 pub mod job_manager;
+pub mod params;
 pub mod scheduler;
 pub mod worker_manager;
@@ -0,0 +1,183 @@
+//! Run parameters and the schema a job declares for them.
+//!
+//! A job may declare the parameters its runs accept, e.g. to re-run it for a date range or a
+//! single file without editing it. Parameters given when a run is triggered are checked against
+//! the schema, completed with defaults and stored on the run, where templates read them as
+//! `{{ params.NAME }}`. Scheduled runs get the defaults.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::plugins::config::{parse_config, ConfigError, PluginConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    /// A `YYYY-MM-DD` string.
+    Date,
+    Array,
+    Object,
+}
+
+impl ParamType {
+    fn describe(self) -> &'static str {
+        match self {
+            ParamType::String => "a string",
+            ParamType::Integer => "an integer",
+            ParamType::Number => "a number",
+            ParamType::Boolean => "a boolean",
+            ParamType::Date => "a date (YYYY-MM-DD)",
+            ParamType::Array => "an array",
+            ParamType::Object => "an object",
+        }
+    }
+
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            ParamType::String => value.is_string(),
+            ParamType::Integer => value.is_i64() || value.is_u64(),
+            ParamType::Number => value.is_number(),
+            ParamType::Boolean => value.is_boolean(),
+            ParamType::Date => value.as_str().is_some_and(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok()),
+            ParamType::Array => value.is_array(),
+            ParamType::Object => value.is_object(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParamSpec {
+    #[serde(rename = "type")]
+    pub param_type: ParamType,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// The parameters a job's runs accept, by name.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ParamSchema(pub BTreeMap<String, ParamSpec>);
+
+impl PluginConfig for ParamSchema {
+    fn config_schema() -> Value {
+        serde_json::json!({ "type": "object" })
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = ConfigError::default();
+        for (name, spec) in &self.0 {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                errors.merge(name, Err(ConfigError::new("", "parameter names may only contain letters, digits and '_'")));
+            }
+            match &spec.default {
+                Some(_) if spec.required => errors.merge(name, Err(ConfigError::new("default", "a required parameter can't have a default"))),
+                Some(default) if !spec.param_type.accepts(default) => {
+                    errors.merge(name, Err(ConfigError::new("default", format!("default must be {}", spec.param_type.describe()))))
+                }
+                _ => {}
+            }
+        }
+        errors.into_result()
+    }
+}
+
+impl ParamSchema {
+    /// Parses and validates a job's `params` schema.
+    pub fn parse(schema: &Value) -> Result<Self, ConfigError> {
+        parse_config(schema)
+    }
+
+    /// The schema stored on a job, which may have none.
+    pub fn from_stored(schema: Option<&Value>) -> Result<Self, ConfigError> {
+        schema.map(Self::parse).transpose().map(Option::unwrap_or_default)
+    }
+
+    pub fn declares(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Checks parameters given for a run and adds the defaults of those left out. Every problem
+    /// is reported, under the parameter's name.
+    pub fn apply(&self, params: &Map<String, Value>) -> Result<Map<String, Value>, ConfigError> {
+        let mut errors = ConfigError::default();
+        for (name, value) in params {
+            match self.0.get(name) {
+                None => errors.merge(name, Err(ConfigError::new("", "unknown parameter"))),
+                Some(spec) if !spec.param_type.accepts(value) => {
+                    errors.merge(name, Err(ConfigError::new("", format!("expected {}", spec.param_type.describe()))))
+                }
+                Some(_) => {}
+            }
+        }
+        let mut applied = params.clone();
+        for (name, spec) in &self.0 {
+            if applied.contains_key(name) {
+                continue;
+            }
+            match &spec.default {
+                Some(default) => {
+                    applied.insert(name.clone(), default.clone());
+                }
+                None if spec.required => errors.merge(name, Err(ConfigError::new("", "required parameter is missing"))),
+                None => {}
+            }
+        }
+        errors.into_result().map(|_| applied)
+    }
+
+    /// The parameters of a run given none, i.e. the defaults.
+    pub fn defaults(&self) -> Map<String, Value> {
+        self.0.iter().filter_map(|(name, spec)| Some((name.clone(), spec.default.clone()?))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> ParamSchema {
+        ParamSchema::parse(&json!({
+            "since": { "type": "date", "required": true },
+            "limit": { "type": "integer", "default": 1000 },
+            "file": { "type": "string" }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_checks_params_and_adds_defaults() {
+        let params = json!({ "since": "2026-10-01" });
+        let applied = schema().apply(params.as_object().unwrap()).unwrap();
+        assert_eq!(Value::Object(applied), json!({ "since": "2026-10-01", "limit": 1000 }));
+
+        let params = json!({ "since": "yesterday", "limit": 1.5, "table": "t" });
+        let errors = schema().apply(params.as_object().unwrap()).unwrap_err();
+        let fields: Vec<(&str, &str)> = errors.errors.iter().map(|e| (e.field.as_str(), e.message.as_str())).collect();
+        assert_eq!(fields, vec![("since", "expected a date (YYYY-MM-DD)"), ("limit", "expected an integer"), ("table", "unknown parameter")]);
+
+        let errors = schema().apply(&Map::new()).unwrap_err();
+        assert_eq!(errors.errors[0].field, "since");
+        assert_eq!(Value::Object(schema().defaults()), json!({ "limit": 1000 }));
+    }
+
+    #[test]
+    fn test_rejects_invalid_schemas() {
+        let field = |schema: Value| ParamSchema::parse(&schema).unwrap_err().errors[0].field.clone();
+        assert_eq!(field(json!({ "since": { "type": "datetime" } })), "since.type");
+        assert_eq!(field(json!({ "limit": { "type": "integer", "default": "ten" } })), "limit.default");
+        assert_eq!(field(json!({ "limit": { "type": "integer", "required": true, "default": 1 } })), "limit.default");
+        assert_eq!(field(json!({ "bad name": { "type": "string" } })), "bad name");
+    }
+}
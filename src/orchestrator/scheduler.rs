@@ -15,10 +15,12 @@
 //! This module provides the `Scheduler` struct, which periodically checks for due jobs
 //! and creates `JobRun` entries in the database for the `WorkerManager` to pick up.
 
+use crate::orchestrator::params::ParamSchema;
 use crate::state::db::Db;
 use anyhow::{Context, Result};
 use cron::Schedule;
 use chrono::Utc;
+use serde_json::Value;
 use std::str::FromStr;
 use std::time::Duration;
 use tokio::time::sleep;
@@ -91,8 +93,12 @@ impl Scheduler {
             if let Some(next_due) = schedule.after(&last_time).next() {
                 if next_due <= now {
                     info!("Scheduler: Scheduling job: {}", job.job_name);
+                    // Scheduled runs take the defaults of the job's parameters.
+                    let params = ParamSchema::from_stored(job.params_schema.as_ref())
+                        .context(format!("Scheduler: Job {} has invalid params", job.job_id))?
+                        .defaults();
                     self.db
-                        .create_job_run(job.job_id, "queued", "scheduler")
+                        .create_job_run(job.job_id, "queued", "scheduler", Some(&Value::Object(params)))
                         .await
                         .context(format!(
                             "Scheduler: Failed to create job run for job {}",
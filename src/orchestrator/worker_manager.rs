@@ -42,7 +42,7 @@ impl WorkerManager {
             if let Some(job_run) = job_run_option {
                 info!("WorkerManager: Found queued job run: {}", job_run.run_id);
                 self.db
-                    .update_job_run_status(job_run.run_id.clone(), "running")
+                    .update_job_run_status(job_run.run_id, "running")
                     .await
                     .context(format!("WorkerManager: Failed to update job run {} status to 'running'", job_run.run_id))?;
                 info!("WorkerManager: Job run {} status set to 'running'.", job_run.run_id);
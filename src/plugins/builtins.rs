@@ -0,0 +1,624 @@
+//! Typed configs, schemas and factories of the built-in plugins, registered in every
+//! `PluginRegistry` created with `PluginRegistry::with_builtins`.
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::plugins::config::{object_schema, ConfigError, PluginConfig};
+use crate::plugins::extractors::api_extractor::ApiExtractor;
+use crate::plugins::extractors::csv_extractor::CsvExtractor;
+use crate::plugins::extractors::parquet_extractor::ParquetExtractor;
+use crate::plugins::extractors::process_extractor::ProcessExtractor;
+use crate::plugins::loaders::csv_loader::{CsvLoader, CsvOptions};
+use crate::plugins::loaders::duckdb_loader::{DuckDBLoader, DuckDbMode};
+use crate::plugins::loaders::http_loader::{HttpAuth, HttpFormat, HttpLoader};
+use crate::plugins::loaders::ndjson_loader::NdjsonLoader;
+use crate::plugins::loaders::postgres_loader::PostgresLoader;
+use crate::plugins::loaders::process_loader::ProcessLoader;
+use crate::plugins::loaders::sqlite_loader::SqliteLoader;
+use crate::plugins::loaders::{single_byte_option, Compression, WriteMode};
+use crate::plugins::process::{ConfigDelivery, ProcessSpec};
+use crate::plugins::registry::PluginRegistry;
+use crate::plugins::transforms::wasm::{module_path, WasmLimits, WasmTransform};
+use crate::plugins::{Extractor, Loader, TaskContext, Transform};
+
+fn string_array() -> Value {
+    json!({ "type": "array", "items": { "type": "string" } })
+}
+
+fn compression_schema() -> Value {
+    json!({ "enum": ["none", "gzip", "gz"], "default": "none" })
+}
+
+fn write_mode_schema() -> Value {
+    json!({ "enum": ["create", "append", "replace", "upsert"], "default": "append" })
+}
+
+/// Reports a parse failure of an option as an error on `field`.
+fn check_field<T>(field: &str, result: Result<T>) -> Result<T, ConfigError> {
+    result.map_err(|e| ConfigError::new(field, format!("{:#}", e)))
+}
+
+/// Requires key columns for upserts.
+fn check_write_mode(mode: WriteMode, key_columns: &[String]) -> Result<(), ConfigError> {
+    if mode == WriteMode::Upsert && key_columns.is_empty() {
+        return Err(ConfigError::new("key_columns", "key_columns must be specified for upsert mode"));
+    }
+    Ok(())
+}
+
+pub fn register(registry: &mut PluginRegistry) {
+    registry.register_extractor("api", "Reads a JSON array of records from an HTTP GET endpoint.", api_extractor);
+    registry.register_extractor("csv", "Reads a CSV file with a header row.", csv_extractor);
+    registry.register_extractor("parquet", "Reads a Parquet file.", parquet_extractor);
+    registry.register_extractor("process", "Runs an executable and reads the Arrow IPC stream it writes to stdout.", process_extractor);
+    register_transforms(registry);
+    registry.register_loader("duckdb", "Writes to a DuckDB table, replacing it atomically or merging as SCD type 2.", duckdb_loader);
+    registry.register_loader("csv", "Exports a CSV file.", csv_loader);
+    registry.register_loader("ndjson", "Exports newline-delimited JSON.", ndjson_loader);
+    registry.register_loader("postgres", "Writes to a PostgreSQL table.", postgres_loader);
+    registry.register_loader("sqlite", "Writes to a SQLite table.", sqlite_loader);
+    registry.register_loader("http", "POSTs the data in batches to an HTTP endpoint, retrying failed batches.", http_loader);
+    registry.register_loader("process", "Runs an executable and writes the data to its stdin as an Arrow IPC stream.", process_loader);
+}
+
+// --- Extractors ---
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiExtractorConfig {
+    pub url: String,
+}
+
+impl PluginConfig for ApiExtractorConfig {
+    fn config_schema() -> Value {
+        object_schema(&["url"], json!({ "url": { "type": "string" } }))
+    }
+}
+
+fn api_extractor(config: ApiExtractorConfig, _ctx: &TaskContext) -> Result<Arc<dyn Extractor + Send + Sync>> {
+    debug!("Worker: Created API extractor for URL: {}", config.url);
+    Ok(Arc::new(ApiExtractor { url: config.url }))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CsvExtractorConfig {
+    pub path: String,
+}
+
+impl PluginConfig for CsvExtractorConfig {
+    fn config_schema() -> Value {
+        object_schema(&["path"], json!({ "path": { "type": "string" } }))
+    }
+}
+
+fn csv_extractor(config: CsvExtractorConfig, _ctx: &TaskContext) -> Result<Arc<dyn Extractor + Send + Sync>> {
+    debug!("Worker: Created CSV extractor for path: {}", config.path);
+    Ok(Arc::new(CsvExtractor { path: config.path }))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParquetExtractorConfig {
+    pub path: String,
+}
+
+impl PluginConfig for ParquetExtractorConfig {
+    fn config_schema() -> Value {
+        object_schema(&["path"], json!({ "path": { "type": "string" } }))
+    }
+}
+
+fn parquet_extractor(config: ParquetExtractorConfig, _ctx: &TaskContext) -> Result<Arc<dyn Extractor + Send + Sync>> {
+    debug!("Worker: Created Parquet extractor for path: {}", config.path);
+    Ok(Arc::new(ParquetExtractor { path: config.path }))
+}
+
+/// Default limit on how long a process plugin may run.
+const PROCESS_TIMEOUT_SECS: u64 = 300;
+
+fn empty_object() -> Value {
+    json!({})
+}
+
+/// Config shared by the `process` extractor and loader.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProcessConfig {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    pub cwd: Option<String>,
+    pub timeout_secs: Option<u64>,
+    /// Settings for the program itself, passed to it as `config` in the JSON document.
+    #[serde(default = "empty_object")]
+    pub config: Value,
+    pub config_via: Option<ConfigDelivery>,
+}
+
+impl ProcessConfig {
+    fn schema(config_via: &[&str], default_via: &str) -> Value {
+        object_schema(
+            &["command"],
+            json!({
+                "command": { "type": "string" },
+                "args": string_array(),
+                "env": { "type": "object", "additionalProperties": { "type": "string" } },
+                "cwd": { "type": "string" },
+                "timeout_secs": { "type": "integer", "minimum": 1, "default": PROCESS_TIMEOUT_SECS },
+                "config": { "type": "object" },
+                "config_via": { "enum": config_via, "default": default_via }
+            }),
+        )
+    }
+
+    fn check(&self) -> Result<(), ConfigError> {
+        if self.command.trim().is_empty() {
+            return Err(ConfigError::new("command", "command must not be empty"));
+        }
+        if self.timeout_secs == Some(0) {
+            return Err(ConfigError::new("timeout_secs", "timeout_secs must be at least 1"));
+        }
+        Ok(())
+    }
+
+    fn spec(self, default_via: ConfigDelivery) -> ProcessSpec {
+        ProcessSpec {
+            command: self.command,
+            args: self.args,
+            env: self.env.into_iter().collect(),
+            cwd: self.cwd,
+            timeout: Duration::from_secs(self.timeout_secs.unwrap_or(PROCESS_TIMEOUT_SECS)),
+            config: self.config,
+            config_via: self.config_via.unwrap_or(default_via),
+        }
+    }
+}
+
+impl PluginConfig for ProcessConfig {
+    fn config_schema() -> Value {
+        ProcessConfig::schema(&["stdin", "arg", "env"], "stdin")
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        self.check()
+    }
+}
+
+fn process_extractor(config: ProcessConfig, ctx: &TaskContext) -> Result<Arc<dyn Extractor + Send + Sync>> {
+    debug!("Worker: Created process extractor for command: {}", config.command);
+    Ok(Arc::new(ProcessExtractor { spec: config.spec(ConfigDelivery::Stdin), ctx: ctx.clone() }))
+}
+
+// --- Transforms ---
+
+fn register_transforms(registry: &mut PluginRegistry) {
+    let steps = [
+        ("select", "Keeps only the listed columns, in order.", object_schema(&["columns"], json!({ "columns": string_array() }))),
+        (
+            "rename",
+            "Renames columns, keyed by current name.",
+            object_schema(&["columns"], json!({ "columns": { "type": "object", "additionalProperties": { "type": "string" } } })),
+        ),
+        (
+            "cast",
+            "Casts columns to a type; non-strict casts turn failures into nulls.",
+            object_schema(
+                &["columns"],
+                json!({
+                    "columns": { "type": "object", "additionalProperties": { "type": "string" } },
+                    "strict": { "type": "boolean", "default": false }
+                }),
+            ),
+        ),
+        ("filter", "Keeps rows for which a SQL expression is true.", object_schema(&["expr"], json!({ "expr": { "type": "string" } }))),
+        (
+            "derive",
+            "Adds or overwrites a column computed from a SQL expression.",
+            object_schema(&["column", "expr"], json!({ "column": { "type": "string" }, "expr": { "type": "string" } })),
+        ),
+        ("drop_nulls", "Drops rows with a null in the listed columns, or in any column.", object_schema(&[], json!({ "columns": string_array() }))),
+        (
+            "sort",
+            "Sorts rows by the listed columns.",
+            object_schema(&["by"], json!({ "by": string_array(), "descending": { "type": "boolean", "default": false } })),
+        ),
+        ("limit", "Keeps the first n rows.", object_schema(&["n"], json!({ "n": { "type": "integer", "minimum": 0 } }))),
+        (
+            "dedupe",
+            "Keeps one row per key, the first or last by an ordering column.",
+            object_schema(
+                &["keys"],
+                json!({
+                    "keys": string_array(),
+                    "order_by": { "type": "string" },
+                    "keep": { "enum": ["first", "last"], "default": "last" }
+                }),
+            ),
+        ),
+        (
+            "sql",
+            "Runs a SELECT query over the data, registered as a table.",
+            object_schema(&["query"], json!({ "query": { "type": "string" }, "table": { "type": "string", "default": "df" } })),
+        ),
+    ];
+    for (name, description, schema) in steps {
+        registry.register_builtin_transform(name, description, schema);
+    }
+    registry.register_transform("wasm", "Runs a sandboxed WebAssembly module from the plugin directory on the data.", wasm_transform);
+}
+
+const WASM_MAX_MEMORY_MB: u64 = 64;
+const WASM_FUEL: u64 = 10_000_000_000;
+const WASM_BATCH_SIZE: usize = 10_000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WasmTransformConfig {
+    /// File name of the module in the plugin directory.
+    pub module: String,
+    /// Settings for the module, passed to each `transform` call as JSON.
+    #[serde(default = "empty_object")]
+    pub config: Value,
+    pub max_memory_mb: Option<u64>,
+    pub fuel: Option<u64>,
+    pub batch_size: Option<usize>,
+}
+
+impl PluginConfig for WasmTransformConfig {
+    fn config_schema() -> Value {
+        object_schema(
+            &["module"],
+            json!({
+                "module": { "type": "string" },
+                "config": { "type": "object" },
+                "max_memory_mb": { "type": "integer", "minimum": 1, "default": WASM_MAX_MEMORY_MB },
+                "fuel": { "type": "integer", "minimum": 1, "default": WASM_FUEL },
+                "batch_size": { "type": "integer", "minimum": 1, "default": WASM_BATCH_SIZE }
+            }),
+        )
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        check_field("module", module_path(&self.module))?;
+        for (field, value) in [("max_memory_mb", self.max_memory_mb), ("fuel", self.fuel), ("batch_size", self.batch_size.map(|n| n as u64))] {
+            if value == Some(0) {
+                return Err(ConfigError::new(field, format!("{} must be at least 1", field)));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn wasm_transform(config: WasmTransformConfig) -> Result<Arc<dyn Transform>> {
+    let limits = WasmLimits {
+        max_memory_bytes: (config.max_memory_mb.unwrap_or(WASM_MAX_MEMORY_MB) << 20) as usize,
+        fuel: config.fuel.unwrap_or(WASM_FUEL),
+    };
+    let batch_size = config.batch_size.unwrap_or(WASM_BATCH_SIZE);
+    debug!("Worker: Created WASM transform for module: {} ({:?})", config.module, limits);
+    Ok(Arc::new(WasmTransform::load(&config.module, &config.config, limits, batch_size)?))
+}
+
+// --- Loaders ---
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuckDbModeName {
+    #[default]
+    Replace,
+    Scd2,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DuckDbLoaderConfig {
+    pub db_path: String,
+    pub table_name: String,
+    #[serde(default)]
+    pub mode: DuckDbModeName,
+    #[serde(default)]
+    pub key_columns: Vec<String>,
+    #[serde(default)]
+    pub tracked_columns: Vec<String>,
+}
+
+impl PluginConfig for DuckDbLoaderConfig {
+    fn config_schema() -> Value {
+        object_schema(
+            &["db_path", "table_name"],
+            json!({
+                "db_path": { "type": "string" },
+                "table_name": { "type": "string" },
+                "mode": { "enum": ["replace", "scd2"], "default": "replace" },
+                "key_columns": string_array(),
+                "tracked_columns": string_array()
+            }),
+        )
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.mode == DuckDbModeName::Scd2 && self.key_columns.is_empty() {
+            return Err(ConfigError::new("key_columns", "key_columns must be specified for scd2 mode"));
+        }
+        Ok(())
+    }
+}
+
+fn duckdb_loader(config: DuckDbLoaderConfig, _ctx: &TaskContext) -> Result<Arc<dyn Loader + Send + Sync>> {
+    let mut loader = DuckDBLoader::new(&config.db_path, &config.table_name);
+    loader.mode = match config.mode {
+        DuckDbModeName::Replace => DuckDbMode::Replace,
+        DuckDbModeName::Scd2 => DuckDbMode::Scd2 {
+            key_columns: config.key_columns,
+            tracked_columns: config.tracked_columns,
+        },
+    };
+    debug!("Worker: Created DuckDB loader for path: {} and table: {} (mode: {:?})", config.db_path, config.table_name, loader.mode);
+    Ok(Arc::new(loader))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CsvLoaderConfig {
+    pub path: String,
+    pub delimiter: Option<String>,
+    pub header: Option<bool>,
+    pub quote_char: Option<String>,
+    pub date_format: Option<String>,
+    pub datetime_format: Option<String>,
+    pub compression: Option<String>,
+}
+
+impl CsvLoaderConfig {
+    fn options(&self) -> Result<CsvOptions, ConfigError> {
+        Ok(CsvOptions {
+            delimiter: check_field("delimiter", single_byte_option(self.delimiter.as_deref(), "delimiter", b','))?,
+            include_header: self.header.unwrap_or(true),
+            quote_char: check_field("quote_char", single_byte_option(self.quote_char.as_deref(), "quote_char", b'"'))?,
+            date_format: self.date_format.clone(),
+            datetime_format: self.datetime_format.clone(),
+            compression: check_field("compression", Compression::parse(self.compression.as_deref()))?,
+        })
+    }
+}
+
+impl PluginConfig for CsvLoaderConfig {
+    fn config_schema() -> Value {
+        object_schema(
+            &["path"],
+            json!({
+                "path": { "type": "string" },
+                "delimiter": { "type": "string", "default": "," },
+                "header": { "type": "boolean", "default": true },
+                "quote_char": { "type": "string", "default": "\"" },
+                "date_format": { "type": "string" },
+                "datetime_format": { "type": "string" },
+                "compression": compression_schema()
+            }),
+        )
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        self.options().map(|_| ())
+    }
+}
+
+fn csv_loader(config: CsvLoaderConfig, ctx: &TaskContext) -> Result<Arc<dyn Loader + Send + Sync>> {
+    let options = config.options()?;
+    debug!("Worker: Created CSV loader for path: {}", config.path);
+    Ok(Arc::new(CsvLoader::new(&config.path, options, ctx.run_id)))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NdjsonLoaderConfig {
+    pub path: String,
+    pub compression: Option<String>,
+}
+
+impl PluginConfig for NdjsonLoaderConfig {
+    fn config_schema() -> Value {
+        object_schema(&["path"], json!({ "path": { "type": "string" }, "compression": compression_schema() }))
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        check_field("compression", Compression::parse(self.compression.as_deref())).map(|_| ())
+    }
+}
+
+fn ndjson_loader(config: NdjsonLoaderConfig, ctx: &TaskContext) -> Result<Arc<dyn Loader + Send + Sync>> {
+    let compression = Compression::parse(config.compression.as_deref())?;
+    debug!("Worker: Created NDJSON loader for path: {}", config.path);
+    Ok(Arc::new(NdjsonLoader::new(&config.path, compression, ctx.run_id)))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PostgresLoaderConfig {
+    pub connection_url: String,
+    pub table_name: String,
+    #[serde(default)]
+    pub mode: WriteMode,
+    #[serde(default)]
+    pub key_columns: Vec<String>,
+}
+
+impl PluginConfig for PostgresLoaderConfig {
+    fn config_schema() -> Value {
+        object_schema(
+            &["connection_url", "table_name"],
+            json!({
+                "connection_url": { "type": "string" },
+                "table_name": { "type": "string" },
+                "mode": write_mode_schema(),
+                "key_columns": string_array()
+            }),
+        )
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        check_write_mode(self.mode, &self.key_columns)
+    }
+}
+
+fn postgres_loader(config: PostgresLoaderConfig, _ctx: &TaskContext) -> Result<Arc<dyn Loader + Send + Sync>> {
+    debug!("Worker: Created PostgreSQL loader for table: {} (mode: {:?})", config.table_name, config.mode);
+    Ok(Arc::new(PostgresLoader::new(&config.connection_url, &config.table_name, config.mode, config.key_columns)))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SqliteLoaderConfig {
+    pub db_path: String,
+    pub table_name: String,
+    #[serde(default)]
+    pub mode: WriteMode,
+    #[serde(default)]
+    pub key_columns: Vec<String>,
+    pub batch_size: Option<usize>,
+}
+
+impl PluginConfig for SqliteLoaderConfig {
+    fn config_schema() -> Value {
+        object_schema(
+            &["db_path", "table_name"],
+            json!({
+                "db_path": { "type": "string" },
+                "table_name": { "type": "string" },
+                "mode": write_mode_schema(),
+                "key_columns": string_array(),
+                "batch_size": { "type": "integer", "minimum": 1, "default": 500 }
+            }),
+        )
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.batch_size == Some(0) {
+            return Err(ConfigError::new("batch_size", "batch_size must be at least 1"));
+        }
+        check_write_mode(self.mode, &self.key_columns)
+    }
+}
+
+fn sqlite_loader(config: SqliteLoaderConfig, _ctx: &TaskContext) -> Result<Arc<dyn Loader + Send + Sync>> {
+    let batch_size = config.batch_size.unwrap_or(500);
+    debug!("Worker: Created SQLite loader for path: {} and table: {} (mode: {:?})", config.db_path, config.table_name, config.mode);
+    Ok(Arc::new(SqliteLoader::new(&config.db_path, &config.table_name, config.mode, config.key_columns, batch_size)))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum HttpAuthConfig {
+    Bearer {
+        token: String,
+    },
+    Basic {
+        username: String,
+        #[serde(default)]
+        password: String,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpLoaderConfig {
+    pub url: String,
+    pub format: Option<String>,
+    pub batch_size: Option<usize>,
+    pub max_retries: Option<u32>,
+    pub backoff_ms: Option<u64>,
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub auth: Option<HttpAuthConfig>,
+}
+
+impl PluginConfig for HttpLoaderConfig {
+    fn config_schema() -> Value {
+        object_schema(
+            &["url"],
+            json!({
+                "url": { "type": "string" },
+                "format": { "enum": ["json", "ndjson"], "default": "json" },
+                "batch_size": { "type": "integer", "minimum": 1, "default": 1000 },
+                "max_retries": { "type": "integer", "minimum": 0, "default": 3 },
+                "backoff_ms": { "type": "integer", "minimum": 0, "default": 500 },
+                "timeout_secs": { "type": "integer", "minimum": 1, "default": 30 },
+                "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+                "auth": {
+                    "type": "object",
+                    "required": ["type"],
+                    "properties": {
+                        "type": { "enum": ["bearer", "basic"] },
+                        "token": { "type": "string" },
+                        "username": { "type": "string" },
+                        "password": { "type": "string" }
+                    }
+                }
+            }),
+        )
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.batch_size == Some(0) {
+            return Err(ConfigError::new("batch_size", "batch_size must be at least 1"));
+        }
+        check_field("format", HttpFormat::parse(self.format.as_deref())).map(|_| ())
+    }
+}
+
+fn http_loader(config: HttpLoaderConfig, _ctx: &TaskContext) -> Result<Arc<dyn Loader + Send + Sync>> {
+    let mut loader = HttpLoader::new(&config.url, HttpFormat::parse(config.format.as_deref())?);
+    if let Some(batch_size) = config.batch_size {
+        loader.batch_size = batch_size;
+    }
+    if let Some(max_retries) = config.max_retries {
+        loader.max_retries = max_retries;
+    }
+    if let Some(backoff_ms) = config.backoff_ms {
+        loader.backoff = Duration::from_millis(backoff_ms);
+    }
+    if let Some(timeout_secs) = config.timeout_secs {
+        loader.timeout = Duration::from_secs(timeout_secs);
+    }
+    loader.headers = config.headers.into_iter().collect();
+    loader.auth = match config.auth {
+        None => HttpAuth::None,
+        Some(HttpAuthConfig::Bearer { token }) => HttpAuth::Bearer(token),
+        Some(HttpAuthConfig::Basic { username, password }) => HttpAuth::Basic { username, password },
+    };
+    debug!("Worker: Created HTTP loader for URL: {}", config.url);
+    Ok(Arc::new(loader))
+}
+
+/// The `process` loader's config. Its stdin carries the data, so the JSON document can't use it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct ProcessLoaderConfig(pub ProcessConfig);
+
+impl PluginConfig for ProcessLoaderConfig {
+    fn config_schema() -> Value {
+        ProcessConfig::schema(&["arg", "env"], "arg")
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.0.config_via == Some(ConfigDelivery::Stdin) {
+            return Err(ConfigError::new("config_via", "a process loader receives the data on stdin; use `arg` or `env`"));
+        }
+        self.0.check()
+    }
+}
+
+fn process_loader(config: ProcessLoaderConfig, ctx: &TaskContext) -> Result<Arc<dyn Loader + Send + Sync>> {
+    debug!("Worker: Created process loader for command: {}", config.0.command);
+    Ok(Arc::new(ProcessLoader { spec: config.0.spec(ConfigDelivery::Arg), ctx: ctx.clone() }))
+}
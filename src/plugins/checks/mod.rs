@@ -0,0 +1,317 @@
+//! Data quality checks evaluated before loading.
+//!
+//! A task's optional `checks` config is a list of assertions about the (transformed) data, each
+//! tagged by `check` and carrying a `severity`. The worker evaluates every check and stores the
+//! outcomes with the run; a failed `error` check fails the task before any loader runs, while a
+//! failed `warn` check is only recorded.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use polars::prelude::*;
+use regex::Regex;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warn,
+    #[default]
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// The assertion made by a check.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "check", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CheckKind {
+    NotNull { column: String },
+    /// No two rows share the same values in `columns`.
+    Unique { columns: Vec<String> },
+    /// Non-null values, compared as text, must be one of `values`.
+    AcceptedValues { column: String, values: Vec<Value> },
+    /// Non-null values must lie within the inclusive bounds.
+    Range {
+        column: String,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    /// Non-null values, as text, must match the pattern.
+    Regex { column: String, pattern: String },
+    RowCount { min: Option<usize>, max: Option<usize> },
+    /// The newest timestamp in `column` is at most `max_age_secs` old.
+    Freshness { column: String, max_age_secs: i64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckSpec {
+    #[serde(flatten)]
+    pub kind: CheckKind,
+    pub severity: Severity,
+}
+
+// `#[serde(flatten)]` can't be combined with `deny_unknown_fields`, so `severity` is taken out
+// by hand and the remaining keys go to `CheckKind`, which rejects any it doesn't know.
+impl<'de> Deserialize<'de> for CheckSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let mut fields = Map::deserialize(deserializer)?;
+        let severity = match fields.remove("severity") {
+            Some(severity) => Severity::deserialize(severity).map_err(D::Error::custom)?,
+            None => Severity::default(),
+        };
+        let kind = CheckKind::deserialize(Value::Object(fields)).map_err(D::Error::custom)?;
+        Ok(CheckSpec { kind, severity })
+    }
+}
+
+/// Outcome of evaluating one check.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckOutcome {
+    pub check_name: String,
+    pub column_name: Option<String>,
+    pub severity: Severity,
+    pub passed: bool,
+    /// Rows violating the check; `None` for checks about the whole frame.
+    pub failing_rows: Option<i64>,
+    pub message: String,
+}
+
+impl CheckKind {
+    fn name(&self) -> &'static str {
+        match self {
+            CheckKind::NotNull { .. } => "not_null",
+            CheckKind::Unique { .. } => "unique",
+            CheckKind::AcceptedValues { .. } => "accepted_values",
+            CheckKind::Range { .. } => "range",
+            CheckKind::Regex { .. } => "regex",
+            CheckKind::RowCount { .. } => "row_count",
+            CheckKind::Freshness { .. } => "freshness",
+        }
+    }
+
+    fn column_name(&self) -> Option<String> {
+        match self {
+            CheckKind::NotNull { column }
+            | CheckKind::AcceptedValues { column, .. }
+            | CheckKind::Range { column, .. }
+            | CheckKind::Regex { column, .. }
+            | CheckKind::Freshness { column, .. } => Some(column.clone()),
+            CheckKind::Unique { columns } => Some(columns.join(", ")),
+            CheckKind::RowCount { .. } => None,
+        }
+    }
+}
+
+impl CheckSpec {
+    /// Checks the spec without looking at any data.
+    pub fn validate(&self) -> Result<()> {
+        match &self.kind {
+            CheckKind::Unique { columns } if columns.is_empty() => Err(anyhow::anyhow!("unique requires at least one column")),
+            CheckKind::Range { min: None, max: None, .. } | CheckKind::RowCount { min: None, max: None } => {
+                Err(anyhow::anyhow!("{} requires min, max or both", self.kind.name()))
+            }
+            CheckKind::Regex { pattern, .. } => Regex::new(pattern)
+                .map(|_| ())
+                .with_context(|| format!("Invalid regex '{}'", pattern)),
+            CheckKind::Freshness { max_age_secs, .. } if *max_age_secs < 0 => {
+                Err(anyhow::anyhow!("max_age_secs must not be negative"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Evaluates the check against the frame. Errors (e.g. a missing column) are reported as a failed check.
+    pub fn evaluate(&self, df: &DataFrame) -> CheckOutcome {
+        let (passed, failing_rows, message) = match self.run(df) {
+            Ok((0, message)) => (true, Some(0), message),
+            Ok((failing, message)) => (false, Some(failing), message),
+            Err(e) => (false, None, format!("{:#}", e)),
+        };
+        CheckOutcome {
+            check_name: self.kind.name().to_string(),
+            column_name: self.kind.column_name(),
+            severity: self.severity,
+            passed,
+            failing_rows: failing_rows.filter(|_| !matches!(self.kind, CheckKind::RowCount { .. } | CheckKind::Freshness { .. })),
+            message,
+        }
+    }
+
+    /// Returns the number of violations (0 when the check passes) and a description.
+    fn run(&self, df: &DataFrame) -> Result<(i64, String)> {
+        match &self.kind {
+            CheckKind::NotNull { column } => {
+                let nulls = df.column(column)?.null_count() as i64;
+                Ok((nulls, format!("{} null value(s)", nulls)))
+            }
+            CheckKind::Unique { columns } => {
+                let distinct = df
+                    .clone()
+                    .lazy()
+                    .select(columns.iter().map(|c| col(c)).collect::<Vec<_>>())
+                    .unique(None, UniqueKeepStrategy::Any)
+                    .collect()?
+                    .height();
+                let duplicates = (df.height() - distinct) as i64;
+                Ok((duplicates, format!("{} duplicate row(s)", duplicates)))
+            }
+            CheckKind::AcceptedValues { column, values } => {
+                let accepted: Vec<String> = values
+                    .iter()
+                    .map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))
+                    .collect();
+                let text = df.column(column)?.cast(&DataType::String)?;
+                let invalid = text.str()?.into_iter().flatten().filter(|v| !accepted.iter().any(|a| a == v)).count() as i64;
+                Ok((invalid, format!("{} value(s) outside the accepted set", invalid)))
+            }
+            CheckKind::Range { column, min, max } => {
+                let numbers = df.column(column)?.strict_cast(&DataType::Float64)?;
+                let out_of_range = numbers
+                    .f64()?
+                    .into_iter()
+                    .flatten()
+                    .filter(|v| min.is_some_and(|m| *v < m) || max.is_some_and(|m| *v > m))
+                    .count() as i64;
+                Ok((out_of_range, format!("{} value(s) out of range", out_of_range)))
+            }
+            CheckKind::Regex { column, pattern } => {
+                let re = Regex::new(pattern)?;
+                let text = df.column(column)?.cast(&DataType::String)?;
+                let mismatched = text.str()?.into_iter().flatten().filter(|v| !re.is_match(v)).count() as i64;
+                Ok((mismatched, format!("{} value(s) not matching '{}'", mismatched, pattern)))
+            }
+            CheckKind::RowCount { min, max } => {
+                let rows = df.height();
+                let ok = min.is_none_or(|m| rows >= m) && max.is_none_or(|m| rows <= m);
+                Ok((if ok { 0 } else { 1 }, format!("{} row(s)", rows)))
+            }
+            CheckKind::Freshness { column, max_age_secs } => {
+                let newest = newest_timestamp(df.column(column)?)?
+                    .with_context(|| format!("Column '{}' has no timestamps", column))?;
+                let age = (Utc::now() - newest).num_seconds();
+                let stale = if age > *max_age_secs { 1 } else { 0 };
+                Ok((stale, format!("newest value {} is {}s old", newest.to_rfc3339(), age)))
+            }
+        }
+    }
+}
+
+/// Latest timestamp in a datetime, date or text column. Text is read as RFC 3339,
+/// `YYYY-MM-DD HH:MM:SS` or `YYYY-MM-DD`; naive values are taken to be UTC.
+fn newest_timestamp(series: &Series) -> Result<Option<DateTime<Utc>>> {
+    let text = series.cast(&DataType::String)?;
+    let newest = text
+        .str()?
+        .into_iter()
+        .flatten()
+        .filter_map(|v| {
+            DateTime::parse_from_rfc3339(v)
+                .map(|t| t.with_timezone(&Utc))
+                .or_else(|_| NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S%.f").map(|t| t.and_utc()))
+                .or_else(|_| NaiveDateTime::parse_from_str(v, "%Y-%m-%dT%H:%M:%S%.f").map(|t| t.and_utc()))
+                .or_else(|_| NaiveDate::parse_from_str(v, "%Y-%m-%d").map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc()))
+                .ok()
+        })
+        .max();
+    Ok(newest)
+}
+
+/// Parses and validates a task's `checks` config, which must be a list of checks.
+pub fn parse_checks(config: &Value) -> Result<Vec<CheckSpec>> {
+    let items = config.as_array().context("checks must be a list")?;
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let spec: CheckSpec =
+                serde_json::from_value(item.clone()).with_context(|| format!("Invalid check {}", i + 1))?;
+            spec.validate().with_context(|| format!("Invalid check {}", i + 1))?;
+            Ok(spec)
+        })
+        .collect()
+}
+
+/// Evaluates every check, in order.
+pub fn run_checks(df: &DataFrame, checks: &[CheckSpec]) -> Vec<CheckOutcome> {
+    checks.iter().map(|check| check.evaluate(df)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_frame() -> DataFrame {
+        let recent = (Utc::now() - chrono::Duration::hours(1)).format("%Y-%m-%d %H:%M:%S").to_string();
+        df!(
+            "id" => &[Some(1i64), Some(2), Some(2), None],
+            "status" => &["paid", "open", "void", "paid"],
+            "amount" => &[5.0f64, 50.0, 150.0, 20.0],
+            "email" => &["a@x.io", "b@x.io", "nope", "c@x.io"],
+            "updated_at" => &["2020-01-01 00:00:00", recent.as_str(), "2021-06-01 12:00:00", "2019-01-01"]
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_evaluates_each_check_kind() {
+        let checks = parse_checks(&json!([
+            { "check": "not_null", "column": "id" },
+            { "check": "unique", "columns": ["id"], "severity": "warn" },
+            { "check": "accepted_values", "column": "status", "values": ["paid", "open"] },
+            { "check": "range", "column": "amount", "min": 0, "max": 100 },
+            { "check": "regex", "column": "email", "pattern": "^[^@]+@[^@]+$" },
+            { "check": "row_count", "min": 1, "max": 3 },
+            { "check": "freshness", "column": "updated_at", "max_age_secs": 7200 },
+            { "check": "not_null", "column": "missing" }
+        ]))
+        .unwrap();
+
+        let outcomes = run_checks(&sample_frame(), &checks);
+
+        let summary: Vec<(&str, bool, Option<i64>)> = outcomes
+            .iter()
+            .map(|o| (o.check_name.as_str(), o.passed, o.failing_rows))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("not_null", false, Some(1)),
+                ("unique", false, Some(1)),
+                ("accepted_values", false, Some(1)),
+                ("range", false, Some(1)),
+                ("regex", false, Some(1)),
+                ("row_count", false, None),
+                ("freshness", true, None),
+                ("not_null", false, None),
+            ]
+        );
+        assert_eq!(outcomes[1].severity, Severity::Warn);
+        assert_eq!(outcomes[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_rejects_invalid_checks() {
+        assert!(parse_checks(&json!([{ "check": "regex", "column": "a", "pattern": "(" }])).is_err());
+        assert!(parse_checks(&json!([{ "check": "range", "column": "a" }])).is_err());
+        assert!(parse_checks(&json!([{ "check": "not_null", "column": "a", "severity": "fatal" }])).is_err());
+        assert!(parse_checks(&json!([{ "check": "sometimes" }])).is_err());
+    }
+
+    #[test]
+    fn test_rejects_misspelled_keys() {
+        let err = parse_checks(&json!([{ "check": "not_null", "column": "a", "severty": "warn" }])).unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown field `severty`"), "{:#}", err);
+        assert!(parse_checks(&json!([{ "check": "not_null", "colum": "a" }])).is_err());
+        assert!(parse_checks(&json!([{ "check": "row_count", "min": 1, "severity": "warn" }])).is_ok());
+    }
+}
@@ -0,0 +1,164 @@
+//! Command tasks, which run a program such as `dbt run`, a cleanup script or a vendor CLI as a
+//! step of a job.
+//!
+//! A task with a `command` config takes the place of an extract-load task in the job's ordered
+//! task list. Its stdout and stderr are kept in the run's log, and its exit code decides the
+//! outcome: codes in `success_exit_codes` (default `[0]`) succeed, codes in `warn_exit_codes`
+//! succeed with a warning, and anything else, or being killed, fails the task.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::warn;
+
+use crate::plugins::config::{object_schema, parse_config, ConfigError, PluginConfig};
+use crate::plugins::process::{run_process, Stdout};
+use crate::plugins::TaskContext;
+
+/// Default limit on how long a command may run.
+pub const COMMAND_TIMEOUT_SECS: u64 = 3600;
+
+fn default_success_exit_codes() -> Vec<i32> {
+    vec![0]
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandTask {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Added to the orchestrator's own environment.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    pub cwd: Option<String>,
+    pub timeout_secs: Option<u64>,
+    #[serde(default = "default_success_exit_codes")]
+    pub success_exit_codes: Vec<i32>,
+    #[serde(default)]
+    pub warn_exit_codes: Vec<i32>,
+}
+
+impl PluginConfig for CommandTask {
+    fn config_schema() -> Value {
+        let codes = json!({ "type": "array", "items": { "type": "integer" } });
+        object_schema(
+            &["command"],
+            json!({
+                "command": { "type": "string" },
+                "args": { "type": "array", "items": { "type": "string" } },
+                "env": { "type": "object", "additionalProperties": { "type": "string" } },
+                "cwd": { "type": "string" },
+                "timeout_secs": { "type": "integer", "minimum": 1, "default": COMMAND_TIMEOUT_SECS },
+                "success_exit_codes": codes.clone(),
+                "warn_exit_codes": codes
+            }),
+        )
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.command.trim().is_empty() {
+            return Err(ConfigError::new("command", "command must not be empty"));
+        }
+        if self.timeout_secs == Some(0) {
+            return Err(ConfigError::new("timeout_secs", "timeout_secs must be at least 1"));
+        }
+        if self.success_exit_codes.is_empty() {
+            return Err(ConfigError::new("success_exit_codes", "at least one exit code must mean success"));
+        }
+        if let Some(code) = self.warn_exit_codes.iter().find(|c| self.success_exit_codes.contains(c)) {
+            return Err(ConfigError::new("warn_exit_codes", format!("exit code {} is also listed in success_exit_codes", code)));
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a command that did not fail its task: `success` or `warn`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandOutcome {
+    pub command: String,
+    pub exit_code: i32,
+    pub status: &'static str,
+    pub duration_ms: i64,
+}
+
+/// Parses and validates a task's `command` config.
+pub fn parse_command(config: &Value) -> Result<CommandTask, ConfigError> {
+    parse_config(config)
+}
+
+impl CommandTask {
+    /// Runs the command, failing on a timeout or an exit code that isn't a success or warning.
+    pub async fn run(&self, ctx: &TaskContext) -> Result<CommandOutcome> {
+        let mut cmd = Command::new(&self.command);
+        cmd.args(&self.args).envs(&self.env);
+        if let Some(cwd) = &self.cwd {
+            cmd.current_dir(cwd);
+        }
+        let timeout = Duration::from_secs(self.timeout_secs.unwrap_or(COMMAND_TIMEOUT_SECS));
+
+        let started = Instant::now();
+        let exit = run_process(cmd, &self.command, timeout, ctx, None, Stdout::Log).await?;
+        let duration_ms = started.elapsed().as_millis() as i64;
+
+        let Some(code) = exit.status.code() else {
+            anyhow::bail!("Command '{}' was terminated by {}{}", self.command, exit.status, exit.stderr_detail());
+        };
+        let status = if self.success_exit_codes.contains(&code) {
+            "success"
+        } else if self.warn_exit_codes.contains(&code) {
+            warn!("Worker: Command '{}' for run {} exited with warning code {}", self.command, ctx.run_id, code);
+            "warn"
+        } else {
+            anyhow::bail!("Command '{}' failed with exit code {}{}", self.command, code, exit.stderr_detail());
+        };
+        Ok(CommandOutcome { command: self.command.clone(), exit_code: code, status, duration_ms })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn ctx() -> TaskContext {
+        TaskContext { job_id: Uuid::new_v4(), run_id: Uuid::new_v4(), task_order: 2, run_log: Default::default() }
+    }
+
+    fn sh(script: &str) -> CommandTask {
+        parse_command(&json!({ "command": "sh", "args": ["-c", script], "warn_exit_codes": [1] })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_exit_codes_decide_the_outcome() {
+        let ctx = ctx();
+        let mut task = sh("echo \"building $TARGET\"; echo done >&2");
+        task.env.insert("TARGET".to_string(), "prod".to_string());
+        let outcome = task.run(&ctx).await.unwrap();
+        assert_eq!((outcome.exit_code, outcome.status), (0, "success"));
+
+        let outcome = sh("exit 1").run(&ctx).await.unwrap();
+        assert_eq!((outcome.exit_code, outcome.status), (1, "warn"));
+
+        let err = sh("echo 'disk full' >&2; exit 2").run(&ctx).await.unwrap_err().to_string();
+        assert_eq!(err, "Command 'sh' failed with exit code 2: disk full");
+
+        // stdout and stderr are read concurrently, so only the order within a stream is fixed.
+        let lines: Vec<(String, String)> = ctx.run_log.take().into_iter().map(|l| (l.stream, l.message)).collect();
+        let stream = |name: &str| lines.iter().filter(|l| l.0 == name).map(|l| l.1.as_str()).collect::<Vec<_>>();
+        assert_eq!(stream("stdout"), vec!["building prod"]);
+        assert_eq!(stream("stderr"), vec!["done", "disk full"]);
+    }
+
+    #[test]
+    fn test_rejects_invalid_commands() {
+        let field = |config: Value| parse_command(&config).unwrap_err().errors[0].field.clone();
+        assert_eq!(field(json!({ "args": ["run"] })), "");
+        assert_eq!(field(json!({ "command": "dbt", "timeout_secs": 0 })), "timeout_secs");
+        assert_eq!(field(json!({ "command": "dbt", "warn_exit_codes": [0] })), "warn_exit_codes");
+        assert_eq!(field(json!({ "command": "dbt", "shell": true })), "shell");
+    }
+}
@@ -0,0 +1,159 @@
+//! Typed plugin configuration and field-level validation errors.
+//!
+//! Every plugin declares its config as a type implementing [`PluginConfig`]. Task configs are
+//! parsed into these types when a job is created, so mistakes are reported per field (e.g.
+//! `tasks[0].loader_config.table_name: missing field`) instead of failing the job's runs.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// A problem with one field of a config. `field` is a path such as `tasks[0].loader_config.mode`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// One or more field errors, reported together.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConfigError {
+    pub errors: Vec<FieldError>,
+}
+
+impl ConfigError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { errors: vec![FieldError { field: field.into(), message: message.into() }] }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Adds the errors of `result`, if any, with their fields nested under `prefix`.
+    pub fn merge(&mut self, prefix: &str, result: Result<(), ConfigError>) {
+        if let Err(e) = result {
+            self.errors.extend(e.errors.into_iter().map(|error| FieldError {
+                field: join_path(prefix, &error.field),
+                message: error.message,
+            }));
+        }
+    }
+
+    /// `Ok(())` when no errors were collected.
+    pub fn into_result(self) -> Result<(), ConfigError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let errors: Vec<String> = self.errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+        write!(f, "Invalid configuration: {}", errors.join("; "))
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn join_path(prefix: &str, field: &str) -> String {
+    match (prefix.is_empty(), field.is_empty()) {
+        (_, true) => prefix.to_string(),
+        (true, false) => field.to_string(),
+        (false, false) if field.starts_with('[') => format!("{}{}", prefix, field),
+        (false, false) => format!("{}.{}", prefix, field),
+    }
+}
+
+/// Config of a plugin, parsed from the JSON object in a task definition.
+pub trait PluginConfig: DeserializeOwned + Send + 'static {
+    /// JSON Schema of the config, listed by `GET /plugins`.
+    fn config_schema() -> Value;
+
+    /// Checks that go beyond the shape of the config, e.g. options that require each other.
+    fn validate(&self) -> Result<(), ConfigError> {
+        Ok(())
+    }
+}
+
+/// Untyped config, for plugins that read the JSON themselves.
+impl PluginConfig for Value {
+    fn config_schema() -> Value {
+        json!({ "type": "object" })
+    }
+}
+
+/// Parses and validates a config, reporting the path of the offending field on failure.
+pub fn parse_config<C: PluginConfig>(config: &Value) -> Result<C, ConfigError> {
+    let parsed: C = serde_path_to_error::deserialize(config).map_err(|e| {
+        let field = match e.path().to_string() {
+            path if path == "." => String::new(),
+            path => path,
+        };
+        ConfigError::new(field, e.inner().to_string())
+    })?;
+    parsed.validate()?;
+    Ok(parsed)
+}
+
+/// JSON Schema of a config object with the given properties. Other properties are rejected.
+pub fn object_schema(required: &[&str], properties: Value) -> Value {
+    json!({ "type": "object", "required": required, "properties": properties, "additionalProperties": false })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Target {
+        table: String,
+        #[serde(default)]
+        columns: Vec<String>,
+    }
+
+    impl PluginConfig for Target {
+        fn config_schema() -> Value {
+            object_schema(&["table"], json!({}))
+        }
+
+        fn validate(&self) -> Result<(), ConfigError> {
+            if self.table.is_empty() {
+                return Err(ConfigError::new("table", "must not be empty"));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_reports_the_offending_field() {
+        let missing = parse_config::<Target>(&json!({})).unwrap_err();
+        let wrong_type = parse_config::<Target>(&json!({ "table": "t", "columns": ["a", 1] })).unwrap_err();
+        let unknown = parse_config::<Target>(&json!({ "table": "t", "colums": [] })).unwrap_err();
+        let empty = parse_config::<Target>(&json!({ "table": "" })).unwrap_err();
+
+        let mut errors = ConfigError::default();
+        for e in [missing, wrong_type, unknown, empty] {
+            errors.merge("tasks[0].loader_config", Err(e));
+        }
+
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "tasks[0].loader_config",
+                "tasks[0].loader_config.columns[1]",
+                "tasks[0].loader_config.colums",
+                "tasks[0].loader_config.table"
+            ]
+        );
+        assert!(errors.errors[0].message.contains("missing field `table`"));
+        assert!(errors.errors[2].message.contains("unknown field `colums`"));
+        assert_eq!(parse_config::<Target>(&json!({ "table": "t", "columns": ["a"] })).unwrap().columns, vec!["a"]);
+    }
+}
@@ -1,8 +1,9 @@
 //! Houses various data extractor implementations.
 //! 
 //! This module contains concrete implementations of the `Extractor` trait for different
-//! data sources, such as APIs, CSV files, and Parquet files.
+//! data sources, such as APIs, CSV files, Parquet files and external programs.
 
 pub mod api_extractor;
 pub mod csv_extractor;
-pub mod parquet_extractor;
\ No newline at end of file
+pub mod parquet_extractor;
+pub mod process_extractor;
\ No newline at end of file
@@ -0,0 +1,30 @@
+//! Extracts data by running an external program.
+//!
+//! This module provides the `ProcessExtractor` struct, which implements the `Extractor` trait
+//! by running a process plugin and reading the Arrow IPC stream it writes to stdout.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use polars::prelude::*;
+use std::io::Cursor;
+
+use crate::plugins::process::{ProcessSpec, Stdout};
+use crate::plugins::{Extractor, TaskContext};
+
+pub struct ProcessExtractor {
+    pub spec: ProcessSpec,
+    pub ctx: TaskContext,
+}
+
+#[async_trait]
+impl Extractor for ProcessExtractor {
+    async fn extract(&self) -> Result<DataFrame> {
+        let stdout = self.spec.run(&self.ctx, None, Stdout::Capture).await?;
+        if stdout.is_empty() {
+            anyhow::bail!("Process '{}' wrote no data to stdout", self.spec.command);
+        }
+        IpcStreamReader::new(Cursor::new(stdout))
+            .finish()
+            .with_context(|| format!("Failed to read an Arrow IPC stream from process '{}'", self.spec.command))
+    }
+}
@@ -0,0 +1,140 @@
+//! Writes data to a delimited text file.
+//!
+//! This module provides the `CsvLoader` struct, which implements the `Loader` trait
+//! to export a Polars DataFrame as a CSV file (optionally gzip-compressed) for consumers
+//! that want flat files rather than warehouse tables.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use polars::prelude::*;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::plugins::loaders::{render_path_template, Compression, ExportWriter};
+use crate::plugins::{LoadReport, Loader};
+
+/// Formatting options for CSV exports.
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    pub include_header: bool,
+    pub quote_char: u8,
+    pub date_format: Option<String>,
+    pub datetime_format: Option<String>,
+    pub compression: Compression,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            include_header: true,
+            quote_char: b'"',
+            date_format: None,
+            datetime_format: None,
+            compression: Compression::None,
+        }
+    }
+}
+
+pub struct CsvLoader {
+    /// Output path, rendered from the task config's templates. The deprecated `{run_id}`,
+    /// `{timestamp}` and `{date}` placeholders are replaced when the file is written.
+    pub path: String,
+    pub options: CsvOptions,
+    pub run_id: Uuid,
+}
+
+impl CsvLoader {
+    /// Convenience constructor.
+    pub fn new(path: impl Into<String>, options: CsvOptions, run_id: Uuid) -> Self {
+        Self {
+            path: path.into(),
+            options,
+            run_id,
+        }
+    }
+}
+
+#[async_trait]
+impl Loader for CsvLoader {
+    async fn load(&self, mut df: DataFrame) -> Result<LoadReport> {
+        let path = render_path_template(&self.path, self.run_id, Utc::now());
+        let rows = df.height();
+        info!(path = %path, rows, "Writing CSV export.");
+
+        let options = self.options.clone();
+        let path_clone = path.clone();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let mut writer = ExportWriter::create(&path_clone, options.compression)?;
+            CsvWriter::new(&mut writer)
+                .include_header(options.include_header)
+                .with_separator(options.delimiter)
+                .with_quote_char(options.quote_char)
+                .with_date_format(options.date_format)
+                .with_datetime_format(options.datetime_format)
+                .finish(&mut df)
+                .with_context(|| format!("Failed to write CSV export '{}'", path_clone))?;
+            writer.finish()
+        })
+        .await??;
+
+        info!(path = %path, rows, "Successfully wrote CSV export.");
+        Ok(LoadReport::file(&path, rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn sample_frame() -> DataFrame {
+        df!(
+            "id" => &[1i64, 2],
+            "name" => &["alice", "bob;jr"]
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_writes_templated_path_with_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let run_id = Uuid::new_v4();
+        let template = dir.path().join("out/users_{run_id}.csv");
+        let options = CsvOptions {
+            delimiter: b';',
+            include_header: false,
+            quote_char: b'\'',
+            ..CsvOptions::default()
+        };
+        let loader = CsvLoader::new(template.to_str().unwrap(), options, run_id);
+
+        loader.load(sample_frame()).await.unwrap();
+
+        let written = dir.path().join(format!("out/users_{}.csv", run_id));
+        let contents = std::fs::read_to_string(written).unwrap();
+        assert_eq!(contents, "1;alice\n2;'bob;jr'\n");
+    }
+
+    #[tokio::test]
+    async fn test_gzip_compression() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.csv.gz");
+        let options = CsvOptions {
+            compression: Compression::Gzip,
+            ..CsvOptions::default()
+        };
+        let loader = CsvLoader::new(path.to_str().unwrap(), options, Uuid::new_v4());
+
+        loader.load(sample_frame()).await.unwrap();
+
+        let mut decoded = String::new();
+        GzDecoder::new(std::fs::File::open(path).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "id,name\n1,alice\n2,bob;jr\n");
+    }
+}
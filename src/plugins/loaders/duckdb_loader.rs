@@ -1,3 +1,14 @@
+//! Loads data into a DuckDB table.
+//!
+//! Replace loads are written to a `<table>__staging` table, validated, and then renamed into
+//! place in a single transaction. The table being replaced is kept as `<table>__backup` until
+//! the next load, so a bad load can be rolled back by hand. For a schema-qualified table such
+//! as `analytics.users`, both live in the same schema, which is created if it doesn't exist.
+//!
+//! In SCD2 mode the staged rows are instead merged into a type 2 slowly changing dimension:
+//! changed rows are closed out with `valid_to` and new versions are inserted with `valid_from`
+//! and `is_current`.
+
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use duckdb::Connection;
@@ -7,11 +18,36 @@ use std::io::{Cursor, Write};
 use tempfile::NamedTempFile;
 use tracing::{debug, info};
 
-use crate::plugins::Loader;
+use crate::plugins::loaders::{quote_column, quote_ident};
+use crate::plugins::{LoadReport, Loader};
+
+/// Suffix of the table a replace load is written to before it is swapped into place.
+const STAGING_SUFFIX: &str = "__staging";
+/// Suffix of the table holding the version replaced by the most recent load.
+const BACKUP_SUFFIX: &str = "__backup";
+
+/// History columns maintained by SCD2 loads.
+pub const VALID_FROM: &str = "valid_from";
+pub const VALID_TO: &str = "valid_to";
+pub const IS_CURRENT: &str = "is_current";
+
+/// How a DuckDB load changes the target table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DuckDbMode {
+    /// Replace the table with the loaded data (staged and swapped in).
+    Replace,
+    /// Slowly changing dimension, type 2, keyed on `key_columns`. A change in any of
+    /// `tracked_columns` (all non-key columns when empty) creates a new version of the row.
+    Scd2 {
+        key_columns: Vec<String>,
+        tracked_columns: Vec<String>,
+    },
+}
 
 pub struct DuckDBLoader {
     pub db_path: String,
     pub table_name: String,
+    pub mode: DuckDbMode,
 }
 
 impl DuckDBLoader {
@@ -20,13 +56,231 @@ impl DuckDBLoader {
         Self {
             db_path: db_path.into(),
             table_name: table_name.into(),
+            mode: DuckDbMode::Replace,
         }
     }
 }
 
+/// Splits a table name into its schema, if it is qualified, and the bare table name.
+fn split_table(table: &str) -> (Option<&str>, &str) {
+    match table.split_once('.') {
+        Some((schema, name)) => (Some(schema), name),
+        None => (None, table),
+    }
+}
+
+fn staging_table(table: &str) -> String {
+    format!("{}{}", table, STAGING_SUFFIX)
+}
+
+/// Name of the backup table kept for `table` after a replace load, for manual rollbacks.
+pub fn backup_table(table: &str) -> String {
+    format!("{}{}", table, BACKUP_SUFFIX)
+}
+
+/// Whether `table` exists, in its own schema if it is qualified and the current one otherwise.
+fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    let (schema, name) = split_table(table);
+    let count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM information_schema.tables WHERE table_schema = COALESCE(?, current_schema()) AND table_name = ?",
+            duckdb::params![schema, name],
+            |row| row.get(0),
+        )
+        .with_context(|| format!("Failed to look up table '{}'", table))?;
+    Ok(count > 0)
+}
+
+/// Checks that the staged table holds exactly the rows and columns of the frame being loaded.
+fn validate_staging(conn: &Connection, staging: &str, expected_rows: usize, expected_columns: &[String]) -> Result<()> {
+    let rows: i64 = conn
+        .query_row(&format!("SELECT COUNT(*) FROM {}", quote_ident(staging)), [], |row| row.get(0))
+        .context("Failed to count staged rows")?;
+    if rows as usize != expected_rows {
+        return Err(anyhow::anyhow!(
+            "Staging validation failed for '{}': expected {} rows, found {}",
+            staging,
+            expected_rows,
+            rows
+        ));
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT column_name FROM information_schema.columns WHERE table_schema = COALESCE(?, current_schema()) AND table_name = ? ORDER BY ordinal_position",
+    )?;
+    let (schema, name) = split_table(staging);
+    let columns = stmt
+        .query_map(duckdb::params![schema, name], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to read staged columns")?;
+    // An empty frame has no header row for read_csv to pick up, so only the row count applies.
+    if expected_rows > 0 && columns != expected_columns {
+        return Err(anyhow::anyhow!(
+            "Staging validation failed for '{}': expected columns {:?}, found {:?}",
+            staging,
+            expected_columns,
+            columns
+        ));
+    }
+    Ok(())
+}
+
+/// Renames the staging table to `table` in one transaction. The current `table` becomes the
+/// backup, replacing the backup left by the previous load. Renamed tables stay in their schema,
+/// so the new names are unqualified.
+fn swap_staging(conn: &mut Connection, table: &str, staging: &str) -> Result<()> {
+    let backup = backup_table(table);
+    let had_table = table_exists(conn, table)?;
+    let tx = conn.transaction().context("Failed to start DuckDB swap transaction")?;
+    tx.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&backup)), [])
+        .with_context(|| format!("Failed to drop backup table '{}'", backup))?;
+    if had_table {
+        tx.execute(&format!("ALTER TABLE {} RENAME TO {}", quote_ident(table), quote_ident(split_table(&backup).1)), [])
+            .with_context(|| format!("Failed to move '{}' to backup table '{}'", table, backup))?;
+    }
+    tx.execute(&format!("ALTER TABLE {} RENAME TO {}", quote_ident(staging), quote_ident(split_table(table).1)), [])
+        .with_context(|| format!("Failed to rename staging table '{}' to '{}'", staging, table))?;
+    tx.commit().context("Failed to commit DuckDB table swap")?;
+    debug!(table, backup = %backup, "Swapped staging table into place.");
+    Ok(())
+}
+
+/// Counts of an SCD2 merge.
+struct Scd2Summary {
+    rows_inserted: usize,
+    rows_closed: usize,
+}
+
+/// Merges the staging table into the SCD2 dimension `table` in one transaction, creating the
+/// table on first load. Staging rows must be unique on the business keys.
+fn merge_scd2(
+    conn: &mut Connection,
+    table: &str,
+    staging: &str,
+    columns: &[String],
+    key_columns: &[String],
+    tracked_columns: &[String],
+) -> Result<Scd2Summary> {
+    if key_columns.is_empty() {
+        return Err(anyhow::anyhow!("key_columns must be specified for scd2 mode"));
+    }
+    for column in key_columns.iter().chain(tracked_columns) {
+        if !columns.contains(column) {
+            return Err(anyhow::anyhow!("Column '{}' is not present in the data", column));
+        }
+    }
+    let tracked: Vec<&String> = if tracked_columns.is_empty() {
+        columns.iter().filter(|c| !key_columns.contains(c)).collect()
+    } else {
+        tracked_columns.iter().collect()
+    };
+
+    let key_list = key_columns.iter().map(|k| quote_column(k)).collect::<Vec<_>>().join(", ");
+    let duplicates: i64 = conn
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM (SELECT {} FROM {} GROUP BY {} HAVING COUNT(*) > 1)",
+                key_list,
+                quote_ident(staging),
+                key_list
+            ),
+            [],
+            |row| row.get(0),
+        )
+        .context("Failed to check business keys for duplicates")?;
+    if duplicates > 0 {
+        return Err(anyhow::anyhow!("{} business key(s) appear more than once in the loaded data", duplicates));
+    }
+
+    let loaded_at = format!("TIMESTAMP '{}'", chrono::Utc::now().format("%Y-%m-%d %H:%M:%S%.6f"));
+    let column_list = columns.iter().map(|c| quote_column(c)).collect::<Vec<_>>().join(", ");
+    let source_columns = columns.iter().map(|c| format!("s.{}", quote_column(c))).collect::<Vec<_>>().join(", ");
+    let history_columns = format!("{}, {}, {}", quote_column(VALID_FROM), quote_column(VALID_TO), quote_column(IS_CURRENT));
+    let key_match = key_columns
+        .iter()
+        .map(|k| format!("t.{0} = s.{0}", quote_column(k)))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+    let tx = conn.transaction().context("Failed to start DuckDB SCD2 transaction")?;
+    if !table_exists(&tx, table)? {
+        tx.execute(
+            &format!(
+                "CREATE TABLE {} AS SELECT {}, {} AS {}, CAST(NULL AS TIMESTAMP) AS {}, TRUE AS {} FROM {} s",
+                quote_ident(table),
+                source_columns,
+                loaded_at,
+                quote_column(VALID_FROM),
+                quote_column(VALID_TO),
+                quote_column(IS_CURRENT),
+                quote_ident(staging)
+            ),
+            [],
+        )
+        .with_context(|| format!("Failed to create SCD2 table '{}'", table))?;
+        let rows_inserted: i64 = tx.query_row(&format!("SELECT COUNT(*) FROM {}", quote_ident(table)), [], |row| row.get(0))?;
+        tx.execute(&format!("DROP TABLE {}", quote_ident(staging)), [])?;
+        tx.commit().context("Failed to commit DuckDB SCD2 load")?;
+        return Ok(Scd2Summary {
+            rows_inserted: rows_inserted as usize,
+            rows_closed: 0,
+        });
+    }
+
+    // Close out current versions whose tracked columns changed. Keys missing from the load are left alone.
+    let changed = tracked
+        .iter()
+        .map(|c| format!("s.{0} IS DISTINCT FROM t.{0}", quote_column(c)))
+        .collect::<Vec<_>>();
+    let rows_closed = if changed.is_empty() {
+        0
+    } else {
+        tx.execute(
+            &format!(
+                "UPDATE {table} AS t SET {valid_to} = {loaded_at}, {is_current} = FALSE \
+                 WHERE t.{is_current} AND EXISTS (SELECT 1 FROM {staging} s WHERE {key_match} AND ({changed}))",
+                table = quote_ident(table),
+                valid_to = quote_column(VALID_TO),
+                is_current = quote_column(IS_CURRENT),
+                loaded_at = loaded_at,
+                staging = quote_ident(staging),
+                key_match = key_match,
+                changed = changed.join(" OR ")
+            ),
+            [],
+        )
+        .with_context(|| format!("Failed to close out changed rows in '{}'", table))?
+    };
+
+    // New keys and the keys just closed out have no current version; insert one for each.
+    let rows_inserted = tx
+        .execute(
+            &format!(
+                "INSERT INTO {table} ({column_list}, {history_columns}) \
+                 SELECT {source_columns}, {loaded_at}, NULL, TRUE FROM {staging} s \
+                 WHERE NOT EXISTS (SELECT 1 FROM {table} t WHERE {key_match} AND t.{is_current})",
+                table = quote_ident(table),
+                column_list = column_list,
+                history_columns = history_columns,
+                source_columns = source_columns,
+                loaded_at = loaded_at,
+                staging = quote_ident(staging),
+                key_match = key_match,
+                is_current = quote_column(IS_CURRENT)
+            ),
+            [],
+        )
+        .with_context(|| format!("Failed to insert new row versions into '{}'", table))?;
+
+    tx.execute(&format!("DROP TABLE {}", quote_ident(staging)), [])?;
+    tx.commit().context("Failed to commit DuckDB SCD2 load")?;
+    debug!(table, rows_inserted, rows_closed, "Merged staging table into SCD2 dimension.");
+    Ok(Scd2Summary { rows_inserted, rows_closed })
+}
+
 #[async_trait]
 impl Loader for DuckDBLoader {
-    async fn load(&self, mut df: DataFrame) -> Result<()> {
+    async fn load(&self, mut df: DataFrame) -> Result<LoadReport> {
         let df_height = df.height();
         info!(
             path = %self.db_path,
@@ -35,10 +289,12 @@ impl Loader for DuckDBLoader {
             "Preparing DuckDB load via CSV bridge."
         );
 
+        let columns: Vec<String> = df.get_column_names().iter().map(|c| c.to_string()).collect();
         let db_path_clone = self.db_path.clone();
         let table_name_clone = self.table_name.clone();
 
-        tokio::task::spawn_blocking(move || -> Result<()> {
+        let mode = self.mode.clone();
+        let details = tokio::task::spawn_blocking(move || -> Result<Option<serde_json::Value>> {
             // 1. Convert Polars DataFrame to CSV string
             let mut buf = Cursor::new(Vec::new());
             CsvWriter::new(&mut buf)
@@ -64,23 +320,56 @@ impl Loader for DuckDBLoader {
             debug!(file_path, "Created temporary IPC file.");
 
             // 3. Open the DuckDB connection and load the data.
-            let conn = Connection::open(&db_path_clone)
+            let mut conn = Connection::open(&db_path_clone)
                 .with_context(|| format!("Failed to open DuckDB database at '{}'", db_path_clone))?;
 
-            // 4. Construct and execute the SQL query to read the CSV
+            // 4. Stage the data next to the live table so readers never see a half-written load.
+            let staging = staging_table(&table_name_clone);
+            if let (Some(schema), _) = split_table(&staging) {
+                conn.execute(&format!("CREATE SCHEMA IF NOT EXISTS {}", quote_ident(schema)), [])
+                    .with_context(|| format!("Failed to create schema '{}'", schema))?;
+            }
             let escaped_file_path = file_path.replace('\\', "/");
             let query = format!(
-                "CREATE OR REPLACE TABLE \"{}\" AS SELECT * FROM read_csv('{}', HEADER=TRUE);",
-                table_name_clone, escaped_file_path
+                "CREATE OR REPLACE TABLE {} AS SELECT * FROM read_csv('{}', HEADER=TRUE);",
+                quote_ident(&staging), escaped_file_path
             );
-            
-            info!("Loading data into DuckDB using read_csv.");
+
+            info!("Loading data into DuckDB staging table using read_csv.");
             match conn.execute(&query, []) {
                 Ok(_) => info!("DuckDB read_csv query successful."),
                 Err(e) => return Err(anyhow::anyhow!("Failed to execute DuckDB read_csv query: {}. Query: '{}'", e, query)),
             }
 
-            Ok(())
+            // 5. Validate the staged copy before it replaces anything.
+            if let Err(e) = validate_staging(&conn, &staging, df_height, &columns) {
+                let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&staging)), []);
+                return Err(e);
+            }
+
+            match mode {
+                // 6. Swap it into place, keeping the previous version as the backup table.
+                DuckDbMode::Replace => {
+                    swap_staging(&mut conn, &table_name_clone, &staging)?;
+                    Ok(None)
+                }
+                // 6. Or merge it into the dimension's history.
+                DuckDbMode::Scd2 { key_columns, tracked_columns } => {
+                    let result = merge_scd2(&mut conn, &table_name_clone, &staging, &columns, &key_columns, &tracked_columns);
+                    let summary = match result {
+                        Ok(summary) => summary,
+                        Err(e) => {
+                            let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&staging)), []);
+                            return Err(e);
+                        }
+                    };
+                    Ok(Some(serde_json::json!({
+                        "mode": "scd2",
+                        "rows_inserted": summary.rows_inserted,
+                        "rows_closed": summary.rows_closed,
+                    })))
+                }
+            }
         })
         .await? // Wait for the blocking task to complete. Propagates panics.
         ?;
@@ -91,6 +380,128 @@ impl Loader for DuckDBLoader {
             "Successfully loaded data into DuckDB table."
         );
 
-        Ok(())
+        let mut report = LoadReport::table(&self.table_name, Some(self.db_path.clone()), df_height);
+        report.details = details;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_ids(conn: &Connection, table: &str) -> Vec<i64> {
+        let mut stmt = conn.prepare(&format!("SELECT id FROM {} ORDER BY id", quote_ident(table))).unwrap();
+        stmt.query_map([], |row| row.get(0)).unwrap().map(|r| r.unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_replace_swaps_staging_and_keeps_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("warehouse.db");
+        let loader = DuckDBLoader::new(db_path.to_str().unwrap(), "users");
+
+        loader.load(df!("id" => &[1i64, 2]).unwrap()).await.unwrap();
+        loader.load(df!("id" => &[3i64]).unwrap()).await.unwrap();
+        loader.load(df!("id" => &[4i64, 5]).unwrap()).await.unwrap();
+
+        let conn = Connection::open(&db_path).unwrap();
+        assert_eq!(read_ids(&conn, "users"), vec![4, 5]);
+        // Only the version replaced by the latest load is kept.
+        assert_eq!(read_ids(&conn, "users__backup"), vec![3]);
+        assert!(!table_exists(&conn, "users__staging").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_scd2_versions_changed_rows_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("warehouse.db");
+        let mut loader = DuckDBLoader::new(db_path.to_str().unwrap(), "dim_customer");
+        loader.mode = DuckDbMode::Scd2 {
+            key_columns: vec!["id".to_string()],
+            tracked_columns: vec!["city".to_string()],
+        };
+
+        let first = df!("id" => &[1i64, 2], "city" => &["Oslo", "Rome"], "visits" => &[1i64, 1]).unwrap();
+        loader.load(first).await.unwrap();
+        // Customer 1 moved, customer 2 only changed an untracked column, customer 3 is new.
+        let second = df!("id" => &[1i64, 2, 3], "city" => &["Bergen", "Rome", "Lima"], "visits" => &[2i64, 5, 1]).unwrap();
+        let report = loader.load(second).await.unwrap();
+
+        let details = report.details.unwrap();
+        assert_eq!(details["rows_closed"], 1);
+        assert_eq!(details["rows_inserted"], 2);
+
+        let conn = Connection::open(&db_path).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT id, city, is_current, valid_to IS NULL FROM dim_customer ORDER BY id, valid_from")
+            .unwrap();
+        let rows: Vec<(i64, String, bool, bool)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (1, "Oslo".to_string(), false, false),
+                (1, "Bergen".to_string(), true, true),
+                (2, "Rome".to_string(), true, true),
+                (3, "Lima".to_string(), true, true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scd2_with_dotted_column_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("warehouse.db");
+        let mut loader = DuckDBLoader::new(db_path.to_str().unwrap(), "dim_customer");
+        loader.mode = DuckDbMode::Scd2 {
+            key_columns: vec!["customer.id".to_string()],
+            tracked_columns: vec!["address.city".to_string()],
+        };
+
+        loader.load(df!("customer.id" => &[1i64], "address.city" => &["Oslo"]).unwrap()).await.unwrap();
+        let report = loader.load(df!("customer.id" => &[1i64], "address.city" => &["Bergen"]).unwrap()).await.unwrap();
+
+        assert_eq!(report.details.unwrap()["rows_closed"], 1);
+        let conn = Connection::open(&db_path).unwrap();
+        let current: String = conn
+            .query_row("SELECT \"address.city\" FROM dim_customer WHERE is_current", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(current, "Bergen");
+    }
+
+    #[tokio::test]
+    async fn test_loads_into_schema_qualified_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("warehouse.db");
+
+        let loader = DuckDBLoader::new(db_path.to_str().unwrap(), "analytics.users");
+        loader.load(df!("id" => &[1i64, 2]).unwrap()).await.unwrap();
+        loader.load(df!("id" => &[3i64]).unwrap()).await.unwrap();
+
+        let mut scd2 = DuckDBLoader::new(db_path.to_str().unwrap(), "analytics.dim_customer");
+        scd2.mode = DuckDbMode::Scd2 {
+            key_columns: vec!["id".to_string()],
+            tracked_columns: Vec::new(),
+        };
+        scd2.load(df!("id" => &[1i64], "city" => &["Oslo"]).unwrap()).await.unwrap();
+        let report = scd2.load(df!("id" => &[1i64], "city" => &["Bergen"]).unwrap()).await.unwrap();
+        assert_eq!(report.details.unwrap()["rows_closed"], 1);
+
+        let conn = Connection::open(&db_path).unwrap();
+        assert_eq!(read_ids(&conn, "analytics.users"), vec![3]);
+        assert_eq!(read_ids(&conn, "analytics.users__backup"), vec![1, 2]);
+        assert!(!table_exists(&conn, "analytics.users__staging").unwrap());
+        assert!(!table_exists(&conn, "users").unwrap());
+        // The dot separates the schema; no table is named `analytics.users` in the default schema.
+        let literal: i64 = conn
+            .query_row("SELECT COUNT(*) FROM information_schema.tables WHERE table_name LIKE 'analytics.%'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(literal, 0);
+        assert_eq!(read_ids(&conn, "analytics.dim_customer"), vec![1, 1]);
+        assert!(!table_exists(&conn, "analytics.dim_customer__staging").unwrap());
     }
 }
@@ -0,0 +1,311 @@
+//! Pushes data to an HTTP endpoint in batches.
+//!
+//! This module provides the `HttpLoader` struct, which implements the `Loader` trait
+//! to POST a Polars DataFrame to a downstream service as a JSON array or NDJSON body,
+//! one batch of rows per request. Batches that fail with a transport error or a status
+//! that may be temporary (408, 429 or 5xx) are retried with exponential backoff; other
+//! statuses fail the batch at once. If some batches still fail, the loader returns a
+//! `PartialLoadError` describing how many were delivered. It is only marked retryable
+//! when nothing was delivered and every failure was temporary, since a retried run sends
+//! all batches again.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use polars::prelude::*;
+use serde_json::json;
+use std::io::Cursor;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+use crate::plugins::{LoadReport, Loader, PartialLoadError};
+
+/// Request body encoding for each batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpFormat {
+    /// A single JSON array of row objects (`application/json`).
+    JsonArray,
+    /// One JSON object per line (`application/x-ndjson`).
+    Ndjson,
+}
+
+impl HttpFormat {
+    /// Parses the `format` option of the HTTP loader config. Missing means a JSON array.
+    pub fn parse(value: Option<&str>) -> Result<Self> {
+        match value {
+            None | Some("json") => Ok(HttpFormat::JsonArray),
+            Some("ndjson") => Ok(HttpFormat::Ndjson),
+            Some(other) => Err(anyhow::anyhow!("Unsupported HTTP body format: {}", other)),
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            HttpFormat::JsonArray => "application/json",
+            HttpFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+/// Authentication attached to every request.
+#[derive(Debug, Clone)]
+pub enum HttpAuth {
+    None,
+    Bearer(String),
+    Basic { username: String, password: String },
+}
+
+pub struct HttpLoader {
+    pub url: String,
+    pub format: HttpFormat,
+    /// Maximum number of rows per request.
+    pub batch_size: usize,
+    pub headers: Vec<(String, String)>,
+    pub auth: HttpAuth,
+    /// Number of retries per batch after the first attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for every further retry.
+    pub backoff: Duration,
+    pub timeout: Duration,
+}
+
+impl HttpLoader {
+    /// Creates a loader with default batching (1000 rows), 3 retries and a 500ms initial backoff.
+    pub fn new(url: impl Into<String>, format: HttpFormat) -> Self {
+        Self {
+            url: url.into(),
+            format,
+            batch_size: 1000,
+            headers: Vec::new(),
+            auth: HttpAuth::None,
+            max_retries: 3,
+            backoff: Duration::from_millis(500),
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    fn encode_batch(&self, batch: &mut DataFrame) -> Result<Vec<u8>> {
+        let mut buf = Cursor::new(Vec::new());
+        let format = match self.format {
+            HttpFormat::JsonArray => JsonFormat::Json,
+            HttpFormat::Ndjson => JsonFormat::JsonLines,
+        };
+        JsonWriter::new(&mut buf)
+            .with_json_format(format)
+            .finish(batch)
+            .context("Failed to serialize batch to JSON")?;
+        Ok(buf.into_inner())
+    }
+
+    /// Sends one batch, retrying transport errors and temporary statuses with exponential
+    /// backoff. Returns the last error if every attempt fails, with whether it was temporary.
+    async fn send_batch(&self, client: &reqwest::Client, body: Vec<u8>, batch_no: usize) -> std::result::Result<(), (anyhow::Error, bool)> {
+        let mut attempt = 0;
+        loop {
+            let mut request = client
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, self.format.content_type())
+                .body(body.clone());
+            for (name, value) in &self.headers {
+                request = request.header(name, value);
+            }
+            request = match &self.auth {
+                HttpAuth::None => request,
+                HttpAuth::Bearer(token) => request.bearer_auth(token),
+                HttpAuth::Basic { username, password } => request.basic_auth(username, Some(password)),
+            };
+
+            let (error, retryable) = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => (anyhow::anyhow!("Endpoint responded with status {}", response.status()), is_retryable(response.status())),
+                Err(e) => (anyhow::Error::new(e).context("Request failed"), true),
+            };
+
+            if !retryable || attempt >= self.max_retries {
+                return Err((error, retryable));
+            }
+            let delay = self.backoff * 2u32.saturating_pow(attempt);
+            warn!(batch = batch_no, attempt = attempt + 1, delay_ms = delay.as_millis() as u64, "HTTP batch failed, retrying: {:#}", error);
+            sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Whether a status may go away on its own: a timeout, rate limiting or a server error.
+fn is_retryable(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::REQUEST_TIMEOUT || status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+#[async_trait]
+impl Loader for HttpLoader {
+    async fn load(&self, df: DataFrame) -> Result<LoadReport> {
+        let df_height = df.height();
+        let batch_size = self.batch_size.max(1);
+        let batches_total = df_height.div_ceil(batch_size);
+        info!(url = %self.url, rows = df_height, batches = batches_total, "Pushing data to HTTP endpoint.");
+
+        let client = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        let mut batches_succeeded = 0;
+        let mut rows_delivered = 0;
+        let mut bytes_sent = 0i64;
+        let mut failures = Vec::new();
+        let mut all_failures_temporary = true;
+        for batch_no in 0..batches_total {
+            let offset = batch_no * batch_size;
+            let mut batch = df.slice(offset as i64, batch_size);
+            let body = self.encode_batch(&mut batch)?;
+            let body_len = body.len() as i64;
+            match self.send_batch(&client, body, batch_no + 1).await {
+                Ok(()) => {
+                    debug!(batch = batch_no + 1, rows = batch.height(), "HTTP batch delivered.");
+                    batches_succeeded += 1;
+                    rows_delivered += batch.height();
+                    bytes_sent += body_len;
+                }
+                Err((e, temporary)) => {
+                    all_failures_temporary &= temporary;
+                    failures.push(format!("batch {}: {:#}", batch_no + 1, e));
+                }
+            }
+        }
+
+        let mut report = LoadReport::table(&self.url, None, rows_delivered);
+        report.bytes_written = Some(bytes_sent);
+        report.details = Some(json!({
+            "batches_total": batches_total,
+            "batches_succeeded": batches_succeeded,
+            "batches_failed": failures.len(),
+        }));
+
+        if !failures.is_empty() {
+            return Err(PartialLoadError {
+                report,
+                message: format!(
+                    "HTTP loader delivered {} of {} batches to {}; failed {}",
+                    batches_succeeded,
+                    batches_total,
+                    self.url,
+                    failures.join("; ")
+                ),
+                retryable: batches_succeeded == 0 && all_failures_temporary,
+            }
+            .into());
+        }
+
+        info!(url = %self.url, rows = rows_delivered, batches = batches_succeeded, "Successfully pushed data to HTTP endpoint.");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Starts a server that rejects the first request to `/ingest`, every request to `/broken`
+    /// and, as invalid, every request to `/invalid`.
+    async fn flaky_server() -> (String, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let invalid_calls = calls.clone();
+        let app = Router::new()
+            .route(
+                "/ingest",
+                post(move |body: String| {
+                    let counter = counter.clone();
+                    async move {
+                        assert!(body.starts_with('['));
+                        if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                            StatusCode::SERVICE_UNAVAILABLE
+                        } else {
+                            StatusCode::OK
+                        }
+                    }
+                }),
+            )
+            .route("/broken", post(|| async { StatusCode::INTERNAL_SERVER_ERROR }))
+            .route(
+                "/invalid",
+                post(move || {
+                    let counter = invalid_calls.clone();
+                    async move {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        StatusCode::UNPROCESSABLE_ENTITY
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}", addr), calls)
+    }
+
+    fn sample_frame() -> DataFrame {
+        df!("id" => &[1i64, 2, 3, 4, 5]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_batches_and_retries() {
+        let (base, calls) = flaky_server().await;
+        let mut loader = HttpLoader::new(format!("{}/ingest", base), HttpFormat::JsonArray);
+        loader.batch_size = 2;
+        loader.backoff = Duration::from_millis(1);
+
+        let report = loader.load(sample_frame()).await.unwrap();
+
+        assert_eq!(report.row_count, 5);
+        assert_eq!(report.details.unwrap()["batches_succeeded"], 3);
+        // Three batches plus one retry of the first.
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_report_partial_failure() {
+        let (base, _) = flaky_server().await;
+        let mut loader = HttpLoader::new(format!("{}/broken", base), HttpFormat::Ndjson);
+        loader.max_retries = 1;
+        loader.backoff = Duration::from_millis(1);
+
+        let err = loader.load(sample_frame()).await.unwrap_err();
+        let partial = err.downcast_ref::<PartialLoadError>().unwrap();
+        assert_eq!(partial.report.row_count, 0);
+        assert_eq!(partial.report.details.as_ref().unwrap()["batches_failed"], 1);
+        // Nothing was delivered and the failure may be temporary, so the run can be retried.
+        assert!(partial.retryable);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_batches_are_not_retried() {
+        let (base, calls) = flaky_server().await;
+        let mut loader = HttpLoader::new(format!("{}/invalid", base), HttpFormat::JsonArray);
+        loader.backoff = Duration::from_millis(1);
+
+        let err = loader.load(sample_frame()).await.unwrap_err();
+        let partial = err.downcast_ref::<PartialLoadError>().unwrap();
+        assert_eq!(partial.report.details.as_ref().unwrap()["batches_failed"], 1);
+        assert!(partial.message.contains("422"), "{}", partial.message);
+        assert!(!partial.retryable);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_partial_delivery_is_not_retryable() {
+        let (base, _) = flaky_server().await;
+        let mut loader = HttpLoader::new(format!("{}/ingest", base), HttpFormat::JsonArray);
+        loader.batch_size = 2;
+        loader.max_retries = 0;
+
+        // The first batch is turned away with a 503 and the other two are delivered.
+        let err = loader.load(sample_frame()).await.unwrap_err();
+        let partial = err.downcast_ref::<PartialLoadError>().unwrap();
+        assert_eq!(partial.report.row_count, 3);
+        assert!(!partial.retryable);
+    }
+}
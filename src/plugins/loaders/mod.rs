@@ -1,6 +1,147 @@
 //! Houses various data loader implementations.
-//! 
+//!
 //! This module contains concrete implementations of the `Loader` trait for different
-//! data destinations, such as DuckDB.
+//! data destinations, such as DuckDB, PostgreSQL, SQLite, flat files (CSV, NDJSON), HTTP endpoints and external programs.
 
-pub mod duckdb_loader;
\ No newline at end of file
+pub mod csv_loader;
+pub mod duckdb_loader;
+pub mod http_loader;
+pub mod ndjson_loader;
+pub mod postgres_loader;
+pub mod process_loader;
+pub mod sqlite_loader;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Compression applied to file exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+}
+
+impl Compression {
+    /// Parses the `compression` option of a file loader config. Missing means no compression.
+    pub fn parse(value: Option<&str>) -> Result<Self> {
+        match value {
+            None | Some("none") => Ok(Compression::None),
+            Some("gzip") | Some("gz") => Ok(Compression::Gzip),
+            Some(other) => Err(anyhow::anyhow!("Unsupported compression: {}", other)),
+        }
+    }
+}
+
+/// How a table loader treats an existing target table, named by the `mode` option of its config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    /// Create the table; fail if it already exists.
+    Create,
+    /// Create the table if missing, then insert the new rows.
+    #[default]
+    Append,
+    /// Drop and recreate the table with the new rows.
+    Replace,
+    /// Insert new rows and update existing ones matched on the key columns.
+    Upsert,
+}
+
+/// Quotes a (possibly schema-qualified) SQL identifier, e.g. `analytics.users` -> `"analytics"."users"`.
+/// Only for table names; use `quote_column` for columns.
+pub fn quote_ident(name: &str) -> String {
+    name.split('.').map(quote_column).collect::<Vec<_>>().join(".")
+}
+
+/// Quotes a single SQL identifier, such as a column name, e.g. `address.city` -> `"address.city"`.
+pub fn quote_column(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Placeholders of export paths from before task configs were templated, and the template
+/// variables replacing them.
+const DEPRECATED_PATH_PLACEHOLDERS: &[(&str, &str)] = &[("{run_id}", "run_id"), ("{timestamp}", "ts_nodash"), ("{date}", "ds")];
+
+/// Replaces the deprecated placeholders in an export path.
+///
+/// Export paths are templated like the rest of the task config (`{{ run_id }}`, `{{ ds }}`,
+/// `{{ ts_nodash }}`, see `crate::templates`) before the loader is built. The older `{run_id}`,
+/// `{timestamp}` (`%Y%m%dT%H%M%S`, UTC) and `{date}` (`%Y-%m-%d`) placeholders are still
+/// replaced, the latter two with the time of the export rather than the run's logical time.
+pub fn render_path_template(template: &str, run_id: Uuid, now: DateTime<Utc>) -> String {
+    for (placeholder, variable) in DEPRECATED_PATH_PLACEHOLDERS {
+        if template.contains(placeholder) {
+            warn!("Export path '{}' uses the deprecated '{}' placeholder; use '{{{{ {} }}}}' instead.", template, placeholder, variable);
+        }
+    }
+    template
+        .replace("{run_id}", &run_id.to_string())
+        .replace("{timestamp}", &now.format("%Y%m%dT%H%M%S").to_string())
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+}
+
+/// Destination file of an export, optionally gzip-compressed.
+pub(crate) enum ExportWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl ExportWriter {
+    /// Creates the export file (and any missing parent directories).
+    pub(crate) fn create(path: &str, compression: Compression) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+            }
+        }
+        let file = File::create(path).with_context(|| format!("Failed to create export file '{}'", path))?;
+        let writer = BufWriter::new(file);
+        Ok(match compression {
+            Compression::None => ExportWriter::Plain(writer),
+            Compression::Gzip => ExportWriter::Gzip(GzEncoder::new(writer, flate2::Compression::default())),
+        })
+    }
+
+    /// Flushes buffered data and writes the gzip trailer, surfacing any I/O error.
+    pub(crate) fn finish(self) -> Result<()> {
+        let mut inner = match self {
+            ExportWriter::Plain(w) => w,
+            ExportWriter::Gzip(encoder) => encoder.finish().context("Failed to finish gzip stream")?,
+        };
+        inner.flush().context("Failed to flush export file")?;
+        Ok(())
+    }
+}
+
+impl Write for ExportWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            ExportWriter::Plain(w) => w.write(buf),
+            ExportWriter::Gzip(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            ExportWriter::Plain(w) => w.flush(),
+            ExportWriter::Gzip(w) => w.flush(),
+        }
+    }
+}
+
+/// Reads a single-byte option such as a delimiter or quote character.
+pub(crate) fn single_byte_option(value: Option<&str>, name: &str, default: u8) -> Result<u8> {
+    match value {
+        None => Ok(default),
+        Some(s) if s.len() == 1 => Ok(s.as_bytes()[0]),
+        Some(s) => Err(anyhow::anyhow!("{} must be a single ASCII character, got '{}'", name, s)),
+    }
+}
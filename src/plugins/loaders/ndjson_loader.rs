@@ -0,0 +1,103 @@
+//! Writes data to a newline-delimited JSON file.
+//!
+//! This module provides the `NdjsonLoader` struct, which implements the `Loader` trait
+//! to export a Polars DataFrame as one JSON object per line (optionally gzip-compressed).
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use polars::prelude::*;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::plugins::loaders::{render_path_template, Compression, ExportWriter};
+use crate::plugins::{LoadReport, Loader};
+
+pub struct NdjsonLoader {
+    /// Output path, rendered from the task config's templates. The deprecated `{run_id}`,
+    /// `{timestamp}` and `{date}` placeholders are replaced when the file is written.
+    pub path: String,
+    pub compression: Compression,
+    pub run_id: Uuid,
+}
+
+impl NdjsonLoader {
+    /// Convenience constructor.
+    pub fn new(path: impl Into<String>, compression: Compression, run_id: Uuid) -> Self {
+        Self {
+            path: path.into(),
+            compression,
+            run_id,
+        }
+    }
+}
+
+#[async_trait]
+impl Loader for NdjsonLoader {
+    async fn load(&self, mut df: DataFrame) -> Result<LoadReport> {
+        let path = render_path_template(&self.path, self.run_id, Utc::now());
+        let rows = df.height();
+        info!(path = %path, rows, "Writing NDJSON export.");
+
+        let compression = self.compression;
+        let path_clone = path.clone();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let mut writer = ExportWriter::create(&path_clone, compression)?;
+            JsonWriter::new(&mut writer)
+                .with_json_format(JsonFormat::JsonLines)
+                .finish(&mut df)
+                .with_context(|| format!("Failed to write NDJSON export '{}'", path_clone))?;
+            writer.finish()
+        })
+        .await??;
+
+        info!(path = %path, rows, "Successfully wrote NDJSON export.");
+        Ok(LoadReport::file(&path, rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn sample_frame() -> DataFrame {
+        df!(
+            "id" => &[1i64, 2],
+            "name" => &["alice", "bob"]
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_writes_one_object_per_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let run_id = Uuid::new_v4();
+        let template = dir.path().join("out/users_{run_id}.ndjson");
+        let loader = NdjsonLoader::new(template.to_str().unwrap(), Compression::None, run_id);
+
+        let report = loader.load(sample_frame()).await.unwrap();
+
+        let written = dir.path().join(format!("out/users_{}.ndjson", run_id));
+        assert_eq!(report.row_count, 2);
+        let contents = std::fs::read_to_string(written).unwrap();
+        assert_eq!(contents, "{\"id\":1,\"name\":\"alice\"}\n{\"id\":2,\"name\":\"bob\"}\n");
+    }
+
+    #[tokio::test]
+    async fn test_gzip_compression() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.ndjson.gz");
+        let loader = NdjsonLoader::new(path.to_str().unwrap(), Compression::Gzip, Uuid::new_v4());
+
+        loader.load(sample_frame()).await.unwrap();
+
+        let mut decoded = String::new();
+        GzDecoder::new(std::fs::File::open(path).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        let lines: Vec<serde_json::Value> = decoded.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines, vec![serde_json::json!({ "id": 1, "name": "alice" }), serde_json::json!({ "id": 2, "name": "bob" })]);
+    }
+}
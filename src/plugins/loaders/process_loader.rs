@@ -0,0 +1,33 @@
+//! Loads data by streaming it into an external program.
+//!
+//! This module provides the `ProcessLoader` struct, which implements the `Loader` trait by
+//! running a process plugin and writing the data to its stdin as an Arrow IPC stream.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use polars::prelude::*;
+
+use crate::plugins::process::{ProcessSpec, Stdout};
+use crate::plugins::{LoadReport, Loader, TaskContext};
+
+pub struct ProcessLoader {
+    pub spec: ProcessSpec,
+    pub ctx: TaskContext,
+}
+
+#[async_trait]
+impl Loader for ProcessLoader {
+    async fn load(&self, mut df: DataFrame) -> Result<LoadReport> {
+        let mut stream = Vec::new();
+        IpcStreamWriter::new(&mut stream)
+            .finish(&mut df)
+            .context("Failed to encode data as an Arrow IPC stream")?;
+        let bytes_written = stream.len() as i64;
+
+        self.spec.run(&self.ctx, Some(stream), Stdout::Log).await?;
+
+        let mut report = LoadReport::table(&self.spec.command, None, df.height());
+        report.bytes_written = Some(bytes_written);
+        Ok(report)
+    }
+}
@@ -0,0 +1,406 @@
+//! Loads data into a table in a SQLite database file.
+//!
+//! This module provides the `SqliteLoader` struct, which implements the `Loader` trait.
+//! Rows are written with batched multi-row `INSERT` statements inside a single transaction,
+//! and the target table is created from the DataFrame's schema when it does not exist.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use polars::prelude::*;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{Connection, QueryBuilder, Sqlite, SqliteConnection};
+use tracing::{debug, info};
+
+use crate::plugins::loaders::{quote_column, quote_ident, WriteMode};
+use crate::plugins::{LoadReport, Loader};
+
+/// SQLite's default limit on bound parameters per statement.
+const MAX_BIND_PARAMS: usize = 32_766;
+
+pub struct SqliteLoader {
+    pub db_path: String,
+    pub table_name: String,
+    pub mode: WriteMode,
+    /// Conflict target for `upsert` mode; also used as the primary key when the table is created.
+    pub key_columns: Vec<String>,
+    /// Maximum number of rows per `INSERT` statement.
+    pub batch_size: usize,
+}
+
+impl SqliteLoader {
+    /// Convenience constructor.
+    pub fn new(
+        db_path: impl Into<String>,
+        table_name: impl Into<String>,
+        mode: WriteMode,
+        key_columns: Vec<String>,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            db_path: db_path.into(),
+            table_name: table_name.into(),
+            mode,
+            key_columns,
+            batch_size,
+        }
+    }
+}
+
+/// Maps a Polars data type to the SQLite column type used when creating tables.
+pub fn sqlite_type(dtype: &DataType) -> &'static str {
+    match dtype {
+        DataType::Boolean
+        | DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => "INTEGER",
+        DataType::Float32 | DataType::Float64 => "REAL",
+        DataType::Binary => "BLOB",
+        _ => "TEXT",
+    }
+}
+
+fn create_table_sql(table: &str, schema: &Schema, key_columns: &[String], if_not_exists: bool) -> String {
+    let mut columns: Vec<String> = schema
+        .iter()
+        .map(|(name, dtype)| format!("{} {}", quote_column(name), sqlite_type(dtype)))
+        .collect();
+    if !key_columns.is_empty() {
+        let keys: Vec<String> = key_columns.iter().map(|k| quote_column(k)).collect();
+        columns.push(format!("PRIMARY KEY ({})", keys.join(", ")));
+    }
+    format!(
+        "CREATE TABLE {}{} ({})",
+        if if_not_exists { "IF NOT EXISTS " } else { "" },
+        quote_ident(table),
+        columns.join(", ")
+    )
+}
+
+/// Builds the unique index `ON CONFLICT` needs on the key columns, for tables the loader didn't
+/// create. SQLite qualifies the index name with the schema, not the table.
+fn unique_index_sql(table: &str, key_columns: &[String]) -> String {
+    let key_list = key_columns.iter().map(|k| quote_column(k)).collect::<Vec<_>>().join(", ");
+    let index_name = format!("ux_{}_upsert_keys", table.replace('.', "_"));
+    let (index, target) = match table.split_once('.') {
+        Some((schema, name)) => (format!("{}.{}", quote_ident(schema), quote_ident(&index_name)), quote_ident(name)),
+        None => (quote_ident(&index_name), quote_ident(table)),
+    };
+    format!("CREATE UNIQUE INDEX IF NOT EXISTS {} ON {} ({})", index, target, key_list)
+}
+
+/// Whether the primary key or a unique index covers exactly the key columns, as `ON CONFLICT`
+/// requires. Tables the loader created have one through their primary key.
+async fn has_unique_index(conn: &mut SqliteConnection, table: &str, key_columns: &[String]) -> Result<bool> {
+    let (schema, name) = table.split_once('.').unwrap_or(("main", table));
+    let mut keys = key_columns.to_vec();
+    keys.sort();
+    let mut primary_key: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?, ?) WHERE pk > 0")
+        .bind(name)
+        .bind(schema)
+        .fetch_all(&mut *conn)
+        .await?;
+    primary_key.sort();
+    if primary_key == keys {
+        return Ok(true);
+    }
+    let indexes: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_index_list(?, ?) WHERE \"unique\" AND NOT partial")
+        .bind(name)
+        .bind(schema)
+        .fetch_all(&mut *conn)
+        .await?;
+    for index in indexes {
+        // Expression columns have no name and never match.
+        let columns: Vec<Option<String>> = sqlx::query_scalar("SELECT name FROM pragma_index_info(?, ?)")
+            .bind(&index)
+            .bind(schema)
+            .fetch_all(&mut *conn)
+            .await?;
+        let mut columns: Vec<String> = columns.into_iter().map(Option::unwrap_or_default).collect();
+        columns.sort();
+        if columns == keys {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Appends a single value to an `INSERT` being built, choosing the closest SQLite storage class.
+fn push_value(builder: &mut sqlx::query_builder::Separated<'_, '_, Sqlite, &'static str>, value: AnyValue) {
+    match value {
+        AnyValue::Null => builder.push_bind(None::<i64>),
+        AnyValue::Boolean(v) => builder.push_bind(v),
+        AnyValue::Int8(v) => builder.push_bind(v as i64),
+        AnyValue::Int16(v) => builder.push_bind(v as i64),
+        AnyValue::Int32(v) => builder.push_bind(v as i64),
+        AnyValue::Int64(v) => builder.push_bind(v),
+        AnyValue::UInt8(v) => builder.push_bind(v as i64),
+        AnyValue::UInt16(v) => builder.push_bind(v as i64),
+        AnyValue::UInt32(v) => builder.push_bind(v as i64),
+        AnyValue::UInt64(v) => match i64::try_from(v) {
+            Ok(v) => builder.push_bind(v),
+            Err(_) => builder.push_bind(v.to_string()),
+        },
+        AnyValue::Float32(v) => builder.push_bind(v as f64),
+        AnyValue::Float64(v) => builder.push_bind(v),
+        AnyValue::String(v) => builder.push_bind(v.to_string()),
+        AnyValue::StringOwned(v) => builder.push_bind(v.to_string()),
+        AnyValue::Binary(v) => builder.push_bind(v.to_vec()),
+        AnyValue::BinaryOwned(v) => builder.push_bind(v),
+        other => builder.push_bind(other.to_string()),
+    };
+}
+
+#[async_trait]
+impl Loader for SqliteLoader {
+    async fn load(&self, df: DataFrame) -> Result<LoadReport> {
+        let df_height = df.height();
+        info!(
+            path = %self.db_path,
+            table = %self.table_name,
+            mode = ?self.mode,
+            rows = df_height,
+            "Preparing SQLite load."
+        );
+
+        let columns: Vec<String> = df.get_column_names().iter().map(|c| c.to_string()).collect();
+        if columns.is_empty() {
+            return Err(anyhow::anyhow!("Cannot load a DataFrame without columns into SQLite"));
+        }
+        if self.mode == WriteMode::Upsert && self.key_columns.is_empty() {
+            return Err(anyhow::anyhow!("key_columns must be specified for upsert mode"));
+        }
+        if let Some(missing) = self.key_columns.iter().find(|k| !columns.contains(k)) {
+            return Err(anyhow::anyhow!("Key column '{}' is not present in the data", missing));
+        }
+
+        let options = SqliteConnectOptions::new()
+            .filename(&self.db_path)
+            .create_if_missing(true);
+        let mut conn = SqliteConnection::connect_with(&options)
+            .await
+            .with_context(|| format!("Failed to open SQLite database at '{}'", self.db_path))?;
+        let mut tx = conn.begin().await.context("Failed to start SQLite transaction")?;
+
+        let table = quote_ident(&self.table_name);
+        let schema = df.schema();
+        let create_sql = match self.mode {
+            WriteMode::Create => create_table_sql(&self.table_name, &schema, &self.key_columns, false),
+            WriteMode::Replace => {
+                sqlx::query(&format!("DROP TABLE IF EXISTS {}", table))
+                    .execute(&mut *tx)
+                    .await
+                    .with_context(|| format!("Failed to drop table '{}'", self.table_name))?;
+                create_table_sql(&self.table_name, &schema, &self.key_columns, false)
+            }
+            WriteMode::Append | WriteMode::Upsert => {
+                create_table_sql(&self.table_name, &schema, &self.key_columns, true)
+            }
+        };
+        debug!(sql = %create_sql, "Ensuring SQLite target table.");
+        sqlx::query(&create_sql)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to create table '{}'", self.table_name))?;
+
+        let key_list = self.key_columns.iter().map(|k| quote_column(k)).collect::<Vec<_>>().join(", ");
+        if self.mode == WriteMode::Upsert {
+            // An existing table may predate the loader; ON CONFLICT needs a unique index on the keys.
+            let covered = has_unique_index(&mut tx, &self.table_name, &self.key_columns)
+                .await
+                .with_context(|| format!("Failed to look up the indexes of '{}'", self.table_name))?;
+            if !covered {
+                sqlx::query(&unique_index_sql(&self.table_name, &self.key_columns))
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to create unique index for upsert keys")?;
+            }
+        }
+
+        let column_list = columns.iter().map(|c| quote_column(c)).collect::<Vec<_>>().join(", ");
+        let conflict_clause = if self.mode == WriteMode::Upsert {
+            let updates: Vec<String> = columns
+                .iter()
+                .filter(|c| !self.key_columns.contains(c))
+                .map(|c| format!("{0} = excluded.{0}", quote_column(c)))
+                .collect();
+            if updates.is_empty() {
+                format!(" ON CONFLICT ({}) DO NOTHING", key_list)
+            } else {
+                format!(" ON CONFLICT ({}) DO UPDATE SET {}", key_list, updates.join(", "))
+            }
+        } else {
+            String::new()
+        };
+
+        let rows_per_batch = self.batch_size.clamp(1, MAX_BIND_PARAMS / columns.len());
+        let series = df.get_columns();
+        let mut offset = 0;
+        while offset < df_height {
+            let end = (offset + rows_per_batch).min(df_height);
+            let mut builder: QueryBuilder<Sqlite> =
+                QueryBuilder::new(format!("INSERT INTO {} ({}) ", table, column_list));
+            builder.push_values(offset..end, |mut row, i| {
+                for s in series {
+                    push_value(&mut row, s.get(i).unwrap_or(AnyValue::Null));
+                }
+            });
+            builder.push(&conflict_clause);
+            builder
+                .build()
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Failed to insert rows {}..{} into '{}'", offset, end, self.table_name))?;
+            debug!(from = offset, to = end, "Inserted SQLite batch.");
+            offset = end;
+        }
+
+        tx.commit().await.context("Failed to commit SQLite load")?;
+
+        info!(
+            table = %self.table_name,
+            rows = df_height,
+            "Successfully loaded data into SQLite table."
+        );
+        Ok(LoadReport::table(&self.table_name, Some(self.db_path.clone()), df_height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read_rows(path: &str, table: &str) -> Vec<(i64, String)> {
+        let options = SqliteConnectOptions::new().filename(path);
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+        sqlx::query_as(&format!("SELECT id, name FROM {} ORDER BY id", table))
+            .fetch_all(&mut conn)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replace_then_upsert() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.sqlite");
+        let path = path.to_str().unwrap();
+        let keys = vec!["id".to_string()];
+
+        let first = df!("id" => &[1i64, 2, 3], "name" => &["a", "b", "c"]).unwrap();
+        SqliteLoader::new(path, "users", WriteMode::Replace, keys.clone(), 2)
+            .load(first)
+            .await
+            .unwrap();
+        let second = df!("id" => &[3i64, 4], "name" => &["cc", "d"]).unwrap();
+        SqliteLoader::new(path, "users", WriteMode::Upsert, keys, 2)
+            .load(second)
+            .await
+            .unwrap();
+
+        let rows = read_rows(path, "users").await;
+        assert_eq!(
+            rows,
+            vec![(1, "a".into()), (2, "b".into()), (3, "cc".into()), (4, "d".into())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upsert_into_schema_qualified_table() {
+        assert_eq!(
+            unique_index_sql("main.users", &["id".to_string()]),
+            "CREATE UNIQUE INDEX IF NOT EXISTS \"main\".\"ux_main_users_upsert_keys\" ON \"users\" (\"id\")"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.sqlite");
+        let path = path.to_str().unwrap();
+        // The table exists without a key, so the loader has to add the unique index itself.
+        let options = SqliteConnectOptions::new().filename(path).create_if_missing(true);
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+        sqlx::query("CREATE TABLE users (id INTEGER, name TEXT)").execute(&mut conn).await.unwrap();
+        conn.close().await.unwrap();
+
+        for name in ["a", "b"] {
+            let df = df!("id" => &[1i64], "name" => &[name]).unwrap();
+            SqliteLoader::new(path, "main.users", WriteMode::Upsert, vec!["id".to_string()], 500)
+                .load(df)
+                .await
+                .unwrap();
+        }
+        assert_eq!(read_rows(path, "users").await, vec![(1, "b".into())]);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_reuses_the_primary_key_it_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.sqlite");
+        let path = path.to_str().unwrap();
+
+        for name in ["a", "b"] {
+            let df = df!("code" => &["x"], "name" => &[name]).unwrap();
+            SqliteLoader::new(path, "codes", WriteMode::Upsert, vec!["code".to_string()], 500)
+                .load(df)
+                .await
+                .unwrap();
+        }
+
+        let options = SqliteConnectOptions::new().filename(path);
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+        let unique_indexes: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pragma_index_list('codes') WHERE \"unique\"")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(unique_indexes, 1);
+        let names: Vec<String> = sqlx::query_scalar("SELECT name FROM codes").fetch_all(&mut conn).await.unwrap();
+        assert_eq!(names, vec!["b".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_with_dotted_column_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.sqlite");
+        let path = path.to_str().unwrap();
+
+        for city in ["Oslo", "Bergen"] {
+            let df = df!("user.id" => &[1i64], "address.city" => &[city]).unwrap();
+            SqliteLoader::new(path, "users", WriteMode::Upsert, vec!["user.id".to_string()], 500)
+                .load(df)
+                .await
+                .unwrap();
+        }
+
+        let options = SqliteConnectOptions::new().filename(path);
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+        let rows: Vec<(i64, String)> = sqlx::query_as("SELECT \"user.id\", \"address.city\" FROM users")
+            .fetch_all(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![(1, "Bergen".into())]);
+    }
+
+    #[tokio::test]
+    async fn test_append_and_create_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.sqlite");
+        let path = path.to_str().unwrap();
+
+        for _ in 0..2 {
+            let df = df!("id" => &[1i64], "name" => &["a"]).unwrap();
+            SqliteLoader::new(path, "events", WriteMode::Append, vec![], 500)
+                .load(df)
+                .await
+                .unwrap();
+        }
+        assert_eq!(read_rows(path, "events").await.len(), 2);
+
+        let df = df!("id" => &[1i64], "name" => &["a"]).unwrap();
+        let result = SqliteLoader::new(path, "events", WriteMode::Create, vec![], 500)
+            .load(df)
+            .await;
+        assert!(result.is_err());
+    }
+}
@@ -2,24 +2,193 @@
 //! 
 //! This module provides traits (`Extractor`, `Loader`) that define the interface for
 //! data source extraction and data destination loading. It also declares sub-modules
-//! for specific extractor and loader implementations.
+//! for specific extractor and loader implementations, for the declarative transforms,
+//! data quality checks and masking applied in between, and for command and SQL tasks.
+//! Plugins are looked up by type name in the `registry`, which library users can extend
+//! with their own implementations.
 
+pub mod builtins;
+pub mod checks;
+pub mod command;
+pub mod config;
 pub mod extractors;
 pub mod loaders;
+pub mod masking;
+pub mod process;
+pub mod registry;
+pub mod sql_task;
+pub mod transforms;
 
 use anyhow::Result;
 use async_trait::async_trait;
-use polars::prelude::DataFrame;
-use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use polars::prelude::{DataFrame, LazyFrame, Schema};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+use uuid::Uuid;
+
+/// Identifies the job run and task a plugin is executing on behalf of.
+#[derive(Debug, Clone)]
+pub struct TaskContext {
+    pub job_id: Uuid,
+    pub run_id: Uuid,
+    pub task_order: i32,
+    /// Output captured for the run, stored by the worker once the attempt finishes.
+    pub run_log: RunLog,
+}
+
+impl TaskContext {
+    /// Records a line of task output (e.g. a process plugin's stderr) in the run's log.
+    pub fn log(&self, stream: &str, message: impl Into<String>) {
+        let message = self.run_log.redact(&message.into());
+        info!("Worker: [run {} task {} {}] {}", self.run_id, self.task_order, stream, message);
+        self.run_log.push(self.task_order, stream, message);
+    }
+}
+
+/// Maximum number of lines kept per attempt; later lines are dropped.
+pub const MAX_RUN_LOG_LINES: usize = 10_000;
+
+/// A line of output captured while running a task.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    pub task_order: i32,
+    /// Where the line came from, e.g. `stdout` or `stderr`.
+    pub stream: String,
+    pub message: String,
+    pub logged_at: DateTime<Utc>,
+}
+
+/// Output captured during one attempt of a run, shared by the tasks' plugins.
+///
+/// The values of secrets resolved for the attempt are replaced with `***` in every line.
+#[derive(Debug, Clone, Default)]
+pub struct RunLog {
+    lines: Arc<Mutex<Vec<LogLine>>>,
+    secrets: Arc<Mutex<Vec<String>>>,
+}
+
+impl RunLog {
+    pub fn push(&self, task_order: i32, stream: &str, message: String) {
+        let message = self.redact(&message);
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() < MAX_RUN_LOG_LINES {
+            lines.push(LogLine { task_order, stream: stream.to_string(), message, logged_at: Utc::now() });
+        }
+    }
+
+    /// Removes and returns the captured lines.
+    pub fn take(&self) -> Vec<LogLine> {
+        std::mem::take(&mut *self.lines.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Registers a secret value to be redacted from this attempt's output.
+    pub fn add_secret(&self, value: &str) {
+        let mut secrets = self.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        if !value.is_empty() && !secrets.iter().any(|s| s == value) {
+            secrets.push(value.to_string());
+            // Longer values first, so a secret containing another is replaced whole.
+            secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        }
+    }
+
+    /// Replaces every registered secret value in `text` with `***`.
+    pub fn redact(&self, text: &str) -> String {
+        let secrets = self.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        secrets.iter().fold(text.to_string(), |text, secret| text.replace(secret.as_str(), "***"))
+    }
+}
 
 #[async_trait]
 pub trait Extractor: Send + Sync {
     async fn extract(&self) -> Result<DataFrame>;
 }
 
+/// What a loader wrote, reported back to the worker for lineage tracking.
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadReport {
+    /// Target table, or the file name for file exports.
+    pub target: String,
+    /// Database or export file the data was written to, if any.
+    pub file_path: Option<String>,
+    pub row_count: i64,
+    pub bytes_written: Option<i64>,
+    /// Loader-specific metrics (e.g. batch counts) surfaced on the run record.
+    pub details: Option<serde_json::Value>,
+}
+
+impl LoadReport {
+    /// Report for a load into a database table.
+    pub fn table(target: impl Into<String>, file_path: Option<String>, row_count: usize) -> Self {
+        Self {
+            target: target.into(),
+            file_path,
+            row_count: row_count as i64,
+            bytes_written: None,
+            details: None,
+        }
+    }
+
+    /// Report for a file export; the size is read back from the written file.
+    pub fn file(path: &str, row_count: usize) -> Self {
+        let bytes_written = std::fs::metadata(path).ok().map(|m| m.len() as i64);
+        let target = std::path::Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string());
+        Self {
+            target,
+            file_path: Some(path.to_string()),
+            row_count: row_count as i64,
+            bytes_written,
+            details: None,
+        }
+    }
+}
+
+/// Error returned by a loader that wrote only part of the data.
+///
+/// The worker downcasts to this type so the rows that did arrive are still recorded.
+#[derive(Debug)]
+pub struct PartialLoadError {
+    pub report: LoadReport,
+    pub message: String,
+    /// Whether retrying the run can load the data without writing any of it twice. When it
+    /// can't, the worker fails the run without retrying.
+    pub retryable: bool,
+}
+
+impl std::fmt::Display for PartialLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for PartialLoadError {}
+
 #[async_trait]
 pub trait Loader: Send + Sync {
-    async fn load(&self, df: DataFrame) -> Result<()>;
+    async fn load(&self, df: DataFrame) -> Result<LoadReport>;
+}
+
+/// A transform step provided by a plugin. Transforms run on a blocking thread, in order with
+/// the task's other transform steps.
+pub trait Transform: Send + Sync {
+    fn apply(&self, lf: LazyFrame) -> Result<LazyFrame>;
+}
+
+/// Stable fingerprint of a frame's column names and types (hex-encoded SHA-256).
+pub fn schema_hash(schema: &Schema) -> String {
+    let mut hasher = Sha256::new();
+    for (name, dtype) in schema.iter() {
+        hasher.update(name.as_bytes());
+        hasher.update(b":");
+        hasher.update(dtype.to_string().as_bytes());
+        hasher.update(b"\n");
+    }
+    format!("{:x}", hasher.finalize())
 }
 
 pub enum PluginType {
@@ -0,0 +1,294 @@
+//! Runs external executables as plugins.
+//!
+//! A process plugin is any program that speaks Arrow IPC: as an extractor it writes the extracted
+//! data to stdout as an IPC stream, as a loader it reads the task's data from stdin as one. The
+//! plugin's own settings are handed over as a JSON document, and every line it prints to stderr
+//! is kept in the run's log.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+
+use crate::plugins::TaskContext;
+
+/// Environment variable holding the JSON document when it is passed with `config_via: env`.
+pub const CONFIG_ENV: &str = "ORC_PLUGIN_CONFIG";
+
+/// How the JSON document reaches the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigDelivery {
+    /// Written to stdin, which is then closed.
+    Stdin,
+    /// Appended as the last command-line argument.
+    Arg,
+    /// Set as the `ORC_PLUGIN_CONFIG` environment variable.
+    Env,
+}
+
+/// What happens to the process's stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stdout {
+    /// Collected and returned, e.g. an extractor's IPC stream.
+    Capture,
+    /// Kept in the run's log line by line.
+    Log,
+}
+
+/// An executable and how to run it.
+#[derive(Debug, Clone)]
+pub struct ProcessSpec {
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub cwd: Option<String>,
+    pub timeout: Duration,
+    /// Plugin settings, passed to the process along with the identity of the run.
+    pub config: Value,
+    pub config_via: ConfigDelivery,
+}
+
+impl ProcessSpec {
+    /// The JSON document handed to the process.
+    fn payload(&self, ctx: &TaskContext) -> String {
+        json!({
+            "job_id": ctx.job_id,
+            "run_id": ctx.run_id,
+            "task_order": ctx.task_order,
+            "config": self.config,
+        })
+        .to_string()
+    }
+
+    /// Runs the process to completion, writing `input` to its stdin.
+    ///
+    /// Returns the captured stdout (empty with `Stdout::Log`). Fails if the process can't be
+    /// started, runs longer than the timeout (it is killed) or exits unsuccessfully.
+    pub async fn run(&self, ctx: &TaskContext, input: Option<Vec<u8>>, stdout: Stdout) -> Result<Vec<u8>> {
+        let payload = self.payload(ctx);
+        let mut cmd = Command::new(&self.command);
+        cmd.args(&self.args).envs(self.env.iter().map(|(k, v)| (k, v)));
+        if let Some(cwd) = &self.cwd {
+            cmd.current_dir(cwd);
+        }
+        let input = match self.config_via {
+            ConfigDelivery::Stdin => Some(payload.into_bytes()),
+            ConfigDelivery::Arg => {
+                cmd.arg(payload);
+                input
+            }
+            ConfigDelivery::Env => {
+                cmd.env(CONFIG_ENV, payload);
+                input
+            }
+        };
+
+        let exit = run_process(cmd, &self.command, self.timeout, ctx, input, stdout).await?;
+        if !exit.status.success() {
+            anyhow::bail!("Process '{}' failed with {}{}", self.command, exit.status, exit.stderr_detail());
+        }
+        Ok(exit.stdout)
+    }
+}
+
+/// How a finished process exited.
+#[derive(Debug)]
+pub struct ProcessExit {
+    pub status: ExitStatus,
+    /// Captured stdout (empty with `Stdout::Log`).
+    pub stdout: Vec<u8>,
+    /// Last non-empty line written to stderr, which usually explains a failure.
+    pub last_stderr: Option<String>,
+}
+
+impl ProcessExit {
+    /// `": <last stderr line>"`, or nothing, for appending to an error message.
+    pub fn stderr_detail(&self) -> String {
+        match &self.last_stderr {
+            Some(line) => format!(": {}", line),
+            None => String::new(),
+        }
+    }
+}
+
+/// Runs `cmd` to completion, writing `input` to its stdin and copying stderr to the run's log.
+///
+/// Fails if the process can't be started or runs longer than `timeout`; judging the exit status
+/// is left to the caller. `name` identifies the process in errors. On Unix the process leads its
+/// own process group, and a timeout kills the whole group, so processes it started in the
+/// background die with it. Elsewhere only the process itself is killed.
+pub async fn run_process(
+    mut cmd: Command,
+    name: &str,
+    timeout: Duration,
+    ctx: &TaskContext,
+    input: Option<Vec<u8>>,
+    stdout: Stdout,
+) -> Result<ProcessExit> {
+    cmd.stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let mut child = cmd.spawn().with_context(|| format!("Failed to start process '{}'", name))?;
+    // Taken now: once the process itself has exited, `child` no longer knows its id, but its
+    // children may still hold the pipes open.
+    let pid = child.id();
+    let stdin = child.stdin.take();
+    let child_stdout = child.stdout.take().context("Process stdout was not captured")?;
+    let child_stderr = child.stderr.take().context("Process stderr was not captured")?;
+
+    let write_input = async move {
+        if let (Some(mut stdin), Some(bytes)) = (stdin, input) {
+            stdin.write_all(&bytes).await?;
+            stdin.shutdown().await?;
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    let read_stdout = async {
+        let mut captured = Vec::new();
+        match stdout {
+            Stdout::Capture => {
+                let mut reader = child_stdout;
+                reader.read_to_end(&mut captured).await?;
+            }
+            Stdout::Log => {
+                log_lines(ctx, "stdout", child_stdout).await?;
+            }
+        }
+        Ok::<_, std::io::Error>(captured)
+    };
+
+    let run = async { tokio::join!(write_input, read_stdout, log_lines(ctx, "stderr", child_stderr), child.wait()) };
+    let (written, captured, last_stderr, status) = match tokio::time::timeout(timeout, run).await {
+        Ok(outcome) => outcome,
+        Err(_) => {
+            kill_process_group(pid);
+            let _ = child.kill().await;
+            anyhow::bail!("Process '{}' timed out after {:?}", name, timeout);
+        }
+    };
+
+    let exit = ProcessExit {
+        status: status.with_context(|| format!("Failed to wait for process '{}'", name))?,
+        stdout: Vec::new(),
+        last_stderr: last_stderr.ok().flatten(),
+    };
+    // A process that exits early may not read all of its input; its exit status explains more.
+    if exit.status.success() {
+        written.with_context(|| format!("Failed to write to stdin of process '{}'", name))?;
+    }
+    let stdout = captured.with_context(|| format!("Failed to read stdout of process '{}'", name))?;
+    Ok(ProcessExit { stdout, ..exit })
+}
+
+/// Kills every process in the group led by the process `pid`.
+#[cfg(unix)]
+fn kill_process_group(pid: Option<u32>) {
+    if let Some(pid) = pid {
+        // SAFETY: killpg only sends a signal; the group is the one created for the process.
+        unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) };
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_pid: Option<u32>) {}
+
+/// Copies each line of `reader` to the run's log and returns the last non-empty one.
+async fn log_lines(ctx: &TaskContext, stream: &str, reader: impl AsyncRead + Unpin) -> std::io::Result<Option<String>> {
+    let mut lines = BufReader::new(reader).lines();
+    let mut last = None;
+    while let Some(line) = lines.next_line().await? {
+        if !line.trim().is_empty() {
+            last = Some(line.clone());
+        }
+        ctx.log(stream, line);
+    }
+    Ok(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::extractors::process_extractor::ProcessExtractor;
+    use crate::plugins::loaders::process_loader::ProcessLoader;
+    use crate::plugins::{Extractor, Loader};
+    use polars::prelude::*;
+    use uuid::Uuid;
+
+    fn ctx() -> TaskContext {
+        TaskContext { job_id: Uuid::new_v4(), run_id: Uuid::new_v4(), task_order: 1, run_log: Default::default() }
+    }
+
+    fn sh(script: &str, config_via: ConfigDelivery) -> ProcessSpec {
+        ProcessSpec {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: Vec::new(),
+            cwd: None,
+            timeout: Duration::from_secs(10),
+            config: json!({ "greeting": "hi" }),
+            config_via,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_round_trips_a_frame_through_processes() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = ctx();
+        let df = df!("id" => [1i64, 2, 3], "name" => ["a", "b", "c"]).unwrap();
+
+        // The loader streams the frame to stdin; the document arrives as the last argument ($0).
+        let script = r#"cat > data.arrow; echo "$0" > config.json; echo loaded >&2"#;
+        let mut spec = sh(script, ConfigDelivery::Arg);
+        spec.cwd = Some(dir.path().to_string_lossy().into_owned());
+        let report = ProcessLoader { spec, ctx: ctx.clone() }.load(df.clone()).await.unwrap();
+        assert_eq!(report.row_count, 3);
+
+        let config: Value = serde_json::from_str(&std::fs::read_to_string(dir.path().join("config.json")).unwrap()).unwrap();
+        assert_eq!(config["config"]["greeting"], "hi");
+        assert_eq!(config["run_id"], ctx.run_id.to_string());
+
+        let mut spec = sh("cat data.arrow", ConfigDelivery::Env);
+        spec.cwd = Some(dir.path().to_string_lossy().into_owned());
+        let extracted = ProcessExtractor { spec, ctx: ctx.clone() }.extract().await.unwrap();
+        assert!(extracted.equals(&df));
+
+        let lines = ctx.run_log.take();
+        assert_eq!(lines.len(), 1);
+        assert_eq!((lines[0].stream.as_str(), lines[0].message.as_str()), ("stderr", "loaded"));
+    }
+
+    #[tokio::test]
+    async fn test_fails_on_exit_code_and_timeout() {
+        let ctx = ctx();
+        let failing = sh("read -r config; echo \"bad input\" >&2; exit 3", ConfigDelivery::Stdin);
+        let err = failing.run(&ctx, None, Stdout::Capture).await.unwrap_err().to_string();
+        assert!(err.contains("exit status: 3"), "{}", err);
+        assert!(err.ends_with("bad input"), "{}", err);
+
+        let mut slow = sh("sleep 5", ConfigDelivery::Env);
+        slow.timeout = Duration::from_millis(200);
+        let err = slow.run(&ctx, None, Stdout::Capture).await.unwrap_err().to_string();
+        assert!(err.contains("timed out"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_processes_started_by_the_plugin() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut slow = sh("(sleep 1; touch survived) & sleep 5", ConfigDelivery::Env);
+        slow.cwd = Some(dir.path().to_string_lossy().into_owned());
+        slow.timeout = Duration::from_millis(200);
+        let err = slow.run(&ctx(), None, Stdout::Capture).await.unwrap_err().to_string();
+        assert!(err.contains("timed out"), "{}", err);
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!dir.path().join("survived").exists());
+    }
+}
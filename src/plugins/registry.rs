@@ -0,0 +1,318 @@
+//! Registry of extractor, transform and loader plugins.
+//!
+//! Every plugin registers a factory under a type name, together with a description and the
+//! type of its config (see `PluginConfig`). The worker builds a task's extractor and loaders
+//! from the `type` of their config, and custom transform steps from their `op`; job creation
+//! validates the same configs up front. The built-in plugins are registered when the registry
+//! is first used; library users can add their own (or replace a built-in) through
+//! [`registry_mut`] before starting the application.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::plugins::builtins;
+use crate::plugins::config::{parse_config, ConfigError, PluginConfig};
+use crate::plugins::transforms::{PluginStep, TransformStep};
+use crate::plugins::{Extractor, Loader, TaskContext, Transform};
+
+/// Extractor config keys read by the worker rather than the plugin.
+const EXTRACTOR_KEYS: &[&str] = &["type"];
+/// Loader config keys read by the worker rather than the plugin.
+const LOADER_KEYS: &[&str] = &["type", "required"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginKind {
+    Extractor,
+    Transform,
+    Loader,
+}
+
+impl PluginKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PluginKind::Extractor => "extractor",
+            PluginKind::Transform => "transform",
+            PluginKind::Loader => "loader",
+        }
+    }
+}
+
+/// Description of a registered plugin, as listed over the API.
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub kind: PluginKind,
+    pub name: String,
+    pub description: String,
+    /// JSON Schema of the plugin's config object.
+    pub config_schema: Value,
+}
+
+type Validator = Arc<dyn Fn(&Value) -> Result<(), ConfigError> + Send + Sync>;
+type ExtractorFactory = Arc<dyn Fn(&Value, &TaskContext) -> Result<Arc<dyn Extractor + Send + Sync>> + Send + Sync>;
+type LoaderFactory = Arc<dyn Fn(&Value, &TaskContext) -> Result<Arc<dyn Loader + Send + Sync>> + Send + Sync>;
+/// Built-in steps are parsed into their own variants; plugin steps are wrapped in `TransformStep::Plugin`.
+type TransformFactory = Arc<dyn Fn(&Value) -> Result<TransformStep> + Send + Sync>;
+
+struct Registration<F> {
+    info: PluginInfo,
+    validate: Validator,
+    factory: F,
+}
+
+#[derive(Default)]
+pub struct PluginRegistry {
+    extractors: BTreeMap<String, Registration<ExtractorFactory>>,
+    transforms: BTreeMap<String, Registration<TransformFactory>>,
+    loaders: BTreeMap<String, Registration<LoaderFactory>>,
+}
+
+fn info<C: PluginConfig>(kind: PluginKind, name: &str, description: &str) -> PluginInfo {
+    PluginInfo {
+        kind,
+        name: name.to_string(),
+        description: description.to_string(),
+        config_schema: C::config_schema(),
+    }
+}
+
+/// The config without the keys the worker reads itself, as seen by the plugin.
+fn plugin_fields(config: &Value, reserved: &[&str]) -> Value {
+    match config {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .filter(|(key, _)| !reserved.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn parse_plugin_config<C: PluginConfig>(config: &Value, reserved: &[&str]) -> Result<C, ConfigError> {
+    parse_config(&plugin_fields(config, reserved))
+}
+
+/// Looks up the registration named by `config[key]`, reporting a missing or unknown name as a field error.
+fn lookup<'a, F>(
+    plugins: &'a BTreeMap<String, Registration<F>>,
+    kind: PluginKind,
+    config: &Value,
+    key: &str,
+) -> Result<&'a Registration<F>, ConfigError> {
+    let name = config[key]
+        .as_str()
+        .ok_or_else(|| ConfigError::new(key, format!("{} {} not specified", kind.as_str(), key)))?;
+    plugins.get(name).ok_or_else(|| {
+        let known: Vec<&str> = plugins.keys().map(String::as_str).collect();
+        ConfigError::new(key, format!("unknown {} {} `{}`, expected one of: {}", kind.as_str(), key, name, known.join(", ")))
+    })
+}
+
+impl PluginRegistry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry holding the built-in plugins.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        builtins::register(&mut registry);
+        registry
+    }
+
+    /// Registers an extractor under `name`, replacing any extractor of the same name. The
+    /// factory receives the task's `extractor_config` parsed as `C`.
+    pub fn register_extractor<C: PluginConfig>(
+        &mut self,
+        name: &str,
+        description: &str,
+        factory: impl Fn(C, &TaskContext) -> Result<Arc<dyn Extractor + Send + Sync>> + Send + Sync + 'static,
+    ) {
+        let factory: ExtractorFactory = Arc::new(move |config, ctx| factory(parse_plugin_config(config, EXTRACTOR_KEYS)?, ctx));
+        let registration = Registration {
+            info: info::<C>(PluginKind::Extractor, name, description),
+            validate: Arc::new(|config| parse_plugin_config::<C>(config, EXTRACTOR_KEYS).map(|_| ())),
+            factory,
+        };
+        self.extractors.insert(name.to_string(), registration);
+    }
+
+    /// Registers a loader under `name`, replacing any loader of the same name. The factory
+    /// receives the loader's entry in `loader_config` parsed as `C`.
+    pub fn register_loader<C: PluginConfig>(
+        &mut self,
+        name: &str,
+        description: &str,
+        factory: impl Fn(C, &TaskContext) -> Result<Arc<dyn Loader + Send + Sync>> + Send + Sync + 'static,
+    ) {
+        let factory: LoaderFactory = Arc::new(move |config, ctx| factory(parse_plugin_config(config, LOADER_KEYS)?, ctx));
+        let registration = Registration {
+            info: info::<C>(PluginKind::Loader, name, description),
+            validate: Arc::new(|config| parse_plugin_config::<C>(config, LOADER_KEYS).map(|_| ())),
+            factory,
+        };
+        self.loaders.insert(name.to_string(), registration);
+    }
+
+    /// Registers a transform step under the `op` name `name`, replacing any step of the same
+    /// name. The factory receives the step's config, without `op`, parsed as `C`.
+    pub fn register_transform<C: PluginConfig>(
+        &mut self,
+        name: &str,
+        description: &str,
+        factory: impl Fn(C) -> Result<Arc<dyn Transform>> + Send + Sync + 'static,
+    ) {
+        let op = name.to_string();
+        let factory: TransformFactory = Arc::new(move |config| {
+            let transform = factory(parse_plugin_config(config, &["op"])?)?;
+            Ok(TransformStep::Plugin(PluginStep { op: op.clone(), transform }))
+        });
+        let registration = Registration {
+            info: info::<C>(PluginKind::Transform, name, description),
+            validate: Arc::new(|config| parse_plugin_config::<C>(config, &["op"]).map(|_| ())),
+            factory,
+        };
+        self.transforms.insert(name.to_string(), registration);
+    }
+
+    /// Registers a built-in step, which is parsed as a whole (including its `op`) into `TransformStep`.
+    pub(crate) fn register_builtin_transform(&mut self, name: &str, description: &str, config_schema: Value) {
+        let mut info = info::<TransformStep>(PluginKind::Transform, name, description);
+        info.config_schema = config_schema;
+        let factory: TransformFactory = Arc::new(|config| Ok(parse_config::<TransformStep>(config)?));
+        let registration = Registration {
+            info,
+            validate: Arc::new(|config| parse_config::<TransformStep>(config).map(|_| ())),
+            factory,
+        };
+        self.transforms.insert(name.to_string(), registration);
+    }
+
+    /// Checks an extractor config without building the extractor.
+    pub fn validate_extractor(&self, config: &Value) -> Result<(), ConfigError> {
+        (lookup(&self.extractors, PluginKind::Extractor, config, "type")?.validate)(config)
+    }
+
+    /// Checks a single loader config without building the loader.
+    pub fn validate_loader(&self, config: &Value) -> Result<(), ConfigError> {
+        (lookup(&self.loaders, PluginKind::Loader, config, "type")?.validate)(config)
+    }
+
+    /// Checks a transform step config without building the step.
+    pub fn validate_transform(&self, config: &Value) -> Result<(), ConfigError> {
+        (lookup(&self.transforms, PluginKind::Transform, config, "op")?.validate)(config)
+    }
+
+    /// Builds the extractor named by the config's `type`.
+    pub fn extractor(&self, config: &Value, ctx: &TaskContext) -> Result<Arc<dyn Extractor + Send + Sync>> {
+        let registration = lookup(&self.extractors, PluginKind::Extractor, config, "type")?;
+        (registration.factory)(config, ctx)
+    }
+
+    /// Builds the loader named by the config's `type`.
+    pub fn loader(&self, config: &Value, ctx: &TaskContext) -> Result<Arc<dyn Loader + Send + Sync>> {
+        let registration = lookup(&self.loaders, PluginKind::Loader, config, "type")?;
+        (registration.factory)(config, ctx)
+    }
+
+    /// Builds the transform step named by the config's `op`.
+    pub fn transform(&self, config: &Value) -> Result<TransformStep> {
+        let registration = lookup(&self.transforms, PluginKind::Transform, config, "op")?;
+        (registration.factory)(config).with_context(|| format!("Invalid {} step", registration.info.name))
+    }
+
+    /// Every registered plugin, ordered by kind and name.
+    pub fn list(&self) -> Vec<PluginInfo> {
+        self.extractors
+            .values()
+            .map(|r| r.info.clone())
+            .chain(self.transforms.values().map(|r| r.info.clone()))
+            .chain(self.loaders.values().map(|r| r.info.clone()))
+            .collect()
+    }
+}
+
+static REGISTRY: LazyLock<RwLock<PluginRegistry>> = LazyLock::new(|| RwLock::new(PluginRegistry::with_builtins()));
+
+/// The application's plugin registry.
+pub fn registry() -> RwLockReadGuard<'static, PluginRegistry> {
+    REGISTRY.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Write access to the application's plugin registry, for registering plugins.
+pub fn registry_mut() -> RwLockWriteGuard<'static, PluginRegistry> {
+    REGISTRY.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::transforms::apply_transforms;
+    use crate::plugins::LoadReport;
+    use async_trait::async_trait;
+    use polars::prelude::*;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    struct CountingLoader;
+
+    #[async_trait]
+    impl Loader for CountingLoader {
+        async fn load(&self, df: DataFrame) -> Result<LoadReport> {
+            Ok(LoadReport::table("counted", None, df.height()))
+        }
+    }
+
+    struct Upper(String);
+
+    #[derive(serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct UpperConfig {
+        column: String,
+    }
+
+    impl PluginConfig for UpperConfig {
+        fn config_schema() -> Value {
+            crate::plugins::config::object_schema(&["column"], json!({ "column": { "type": "string" } }))
+        }
+    }
+
+    impl Transform for Upper {
+        fn apply(&self, lf: LazyFrame) -> Result<LazyFrame> {
+            Ok(lf.with_column(col(&self.0).str().to_uppercase()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_plugins_are_built_by_name() {
+        let mut registry = PluginRegistry::with_builtins();
+        registry.register_loader("counting", "Counts rows.", |_: Value, _| Ok(Arc::new(CountingLoader)));
+        registry.register_transform("upper", "Upper-cases a column.", |config: UpperConfig| Ok(Arc::new(Upper(config.column))));
+        let ctx = TaskContext { job_id: Uuid::new_v4(), run_id: Uuid::new_v4(), task_order: 1, run_log: Default::default() };
+
+        let loader = registry.loader(&json!({ "type": "counting" }), &ctx).unwrap();
+        let step = registry.transform(&json!({ "op": "upper", "column": "name" })).unwrap();
+        let (df, _) = apply_transforms(df!("name" => &["ann", "bob"]).unwrap(), &[step]).unwrap();
+
+        assert_eq!(df.column("name").unwrap().str().unwrap().get(1), Some("BOB"));
+        assert_eq!(loader.load(df).await.unwrap().row_count, 2);
+        assert!(registry.transform(&json!({ "op": "upper" })).is_err());
+        let error = registry.validate_transform(&json!({ "op": "upper", "columns": "name" })).unwrap_err();
+        assert_eq!(error.errors[0].field, "columns");
+        assert!(error.errors[0].message.contains("unknown field `columns`"));
+        let error = registry.validate_loader(&json!({ "type": "warehouse" })).unwrap_err();
+        assert_eq!(error.errors[0].field, "type");
+        assert!(error.errors[0].message.contains("expected one of: counting, csv, duckdb"));
+        assert!(registry.loader(&json!({ "type": "teleport" }), &ctx).is_err());
+        let listed: Vec<(PluginKind, String)> = registry.list().into_iter().map(|p| (p.kind, p.name)).collect();
+        assert!(listed.contains(&(PluginKind::Loader, "counting".to_string())));
+        assert!(listed.contains(&(PluginKind::Extractor, "csv".to_string())));
+        assert!(listed.contains(&(PluginKind::Transform, "select".to_string())));
+    }
+}
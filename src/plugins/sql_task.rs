@@ -0,0 +1,207 @@
+//! SQL tasks, which transform data already in a DuckDB warehouse.
+//!
+//! A task with a `sql` config runs statements against a DuckDB database instead of extracting
+//! and loading data, e.g. to build `analytics.*` models from the raw tables loaded by earlier
+//! tasks of the job. The statements are given inline or read from a `.sql` file when the task
+//! runs, and are executed in order in one transaction: if any statement fails, none of them
+//! take effect. The rows affected by each statement are recorded in the run's metrics.
+
+use anyhow::{Context, Result};
+use duckdb::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Instant;
+
+use crate::plugins::config::{object_schema, parse_config, ConfigError, PluginConfig};
+use crate::plugins::TaskContext;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SqlTask {
+    pub db_path: String,
+    /// Statements run in order. Each entry may hold several `;`-separated statements.
+    #[serde(default)]
+    pub statements: Vec<String>,
+    /// A `.sql` file of `;`-separated statements, read when the task runs.
+    pub file: Option<String>,
+}
+
+impl PluginConfig for SqlTask {
+    fn config_schema() -> Value {
+        object_schema(
+            &["db_path"],
+            json!({
+                "db_path": { "type": "string" },
+                "statements": { "type": "array", "items": { "type": "string" } },
+                "file": { "type": "string" }
+            }),
+        )
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        match (self.statements.is_empty(), &self.file) {
+            (true, None) => Err(ConfigError::new("statements", "either statements or file must be given")),
+            (false, Some(_)) => Err(ConfigError::new("file", "statements and file can't both be given")),
+            _ if self.statements.iter().all(|s| split_statements(s).is_empty()) && self.file.is_none() => {
+                Err(ConfigError::new("statements", "statements contain no SQL"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Rows affected by one statement of a SQL task.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementOutcome {
+    /// Position of the statement, starting at 1.
+    pub statement: usize,
+    /// Rows inserted, updated or deleted; statements such as `CREATE` report 0.
+    pub rows_affected: usize,
+    pub duration_ms: i64,
+}
+
+/// Outcome of a SQL task whose transaction was committed.
+#[derive(Debug, Clone, Serialize)]
+pub struct SqlOutcome {
+    pub db_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    pub statements: Vec<StatementOutcome>,
+}
+
+/// Parses and validates a task's `sql` config.
+pub fn parse_sql_task(config: &Value) -> Result<SqlTask, ConfigError> {
+    parse_config(config)
+}
+
+/// Splits a script into statements on `;`, ignoring semicolons in quotes and comments.
+/// Empty statements are dropped.
+pub fn split_statements(script: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = script.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                current.push(c);
+                // A doubled quote inside a quoted string is an escaped quote, which this handles
+                // as the string closing and immediately reopening.
+                for q in chars.by_ref() {
+                    current.push(q);
+                    if q == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for q in chars.by_ref() {
+                    if q == '\n' {
+                        current.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for q in chars.by_ref() {
+                    if previous == '*' && q == '/' {
+                        break;
+                    }
+                    previous = q;
+                }
+                current.push(' ');
+            }
+            ';' => statements.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    statements.push(current);
+    statements.into_iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+}
+
+impl SqlTask {
+    /// The statements to run, reading the file if the task uses one.
+    fn load_statements(&self) -> Result<Vec<String>> {
+        let statements: Vec<String> = match &self.file {
+            Some(file) => {
+                let script = std::fs::read_to_string(file).with_context(|| format!("Failed to read SQL file '{}'", file))?;
+                split_statements(&script)
+            }
+            None => self.statements.iter().flat_map(|s| split_statements(s)).collect(),
+        };
+        if statements.is_empty() {
+            anyhow::bail!("No SQL statements to run");
+        }
+        Ok(statements)
+    }
+
+    /// Runs the statements in one transaction, rolling all of them back if one fails.
+    /// Blocks on DuckDB; call from a blocking thread.
+    pub fn run(&self, ctx: &TaskContext) -> Result<SqlOutcome> {
+        let statements = self.load_statements()?;
+        let mut conn = Connection::open(&self.db_path).with_context(|| format!("Failed to open DuckDB database '{}'", self.db_path))?;
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        let mut outcomes = Vec::with_capacity(statements.len());
+        for (i, sql) in statements.iter().enumerate() {
+            let started = Instant::now();
+            let rows_affected = tx.execute(sql, []).with_context(|| {
+                format!("Statement {} of {} failed; the transaction was rolled back", i + 1, statements.len())
+            })?;
+            let duration_ms = started.elapsed().as_millis() as i64;
+            ctx.log("sql", format!("Statement {}: {} row(s) affected in {} ms", i + 1, rows_affected, duration_ms));
+            outcomes.push(StatementOutcome { statement: i + 1, rows_affected, duration_ms });
+        }
+        tx.commit().context("Failed to commit transaction")?;
+        Ok(SqlOutcome { db_path: self.db_path.clone(), file: self.file.clone(), statements: outcomes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn ctx() -> TaskContext {
+        TaskContext { job_id: Uuid::new_v4(), run_id: Uuid::new_v4(), task_order: 2, run_log: Default::default() }
+    }
+
+    #[test]
+    fn test_splits_statements_outside_quotes_and_comments() {
+        let script = "-- build models; carefully\nCREATE SCHEMA s;\nINSERT INTO t VALUES ('a;b', \"c;d\") /* x; y */;\n\n;";
+        assert_eq!(split_statements(script), vec!["CREATE SCHEMA s", "INSERT INTO t VALUES ('a;b', \"c;d\")"]);
+    }
+
+    #[test]
+    fn test_runs_statements_in_one_transaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("warehouse.db").to_string_lossy().into_owned();
+        let ctx = ctx();
+        let task = |statements: Value| parse_sql_task(&json!({ "db_path": db_path, "statements": statements })).unwrap();
+
+        let outcome = task(json!([
+            "CREATE TABLE raw_posts AS SELECT * FROM range(5) r(id)",
+            "CREATE SCHEMA analytics; CREATE TABLE analytics.posts (id BIGINT)",
+            "INSERT INTO analytics.posts SELECT id FROM raw_posts WHERE id > 1"
+        ]))
+        .run(&ctx)
+        .unwrap();
+        let rows: Vec<usize> = outcome.statements.iter().map(|s| s.rows_affected).collect();
+        assert_eq!(rows, vec![0, 0, 0, 3]);
+
+        // A failing statement rolls back the ones before it.
+        let err = task(json!(["DELETE FROM analytics.posts", "SELECT * FROM missing_table"])).run(&ctx).unwrap_err();
+        assert!(err.to_string().contains("Statement 2 of 2 failed"));
+        let conn = Connection::open(&db_path).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM analytics.posts", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_rejects_invalid_sql_tasks() {
+        let field = |config: Value| parse_sql_task(&config).unwrap_err().errors[0].field.clone();
+        assert_eq!(field(json!({ "db_path": "w.db" })), "statements");
+        assert_eq!(field(json!({ "db_path": "w.db", "statements": ["SELECT 1"], "file": "m.sql" })), "file");
+        assert_eq!(field(json!({ "db_path": "w.db", "statements": [" ; -- nothing"] })), "statements");
+    }
+}
@@ -0,0 +1,386 @@
+//! Declarative transforms applied between extraction and loading.
+//!
+//! A task's optional `transform` config is a list of steps, each tagged by `op`. The steps are
+//! parsed and validated when the job is created, and applied in order on a Polars `LazyFrame`
+//! by the worker before the data is handed to the loaders. Filter and derive expressions are
+//! written as SQL expressions (e.g. `"amount > 0 AND status = 'paid'"`), and a `sql` step runs
+//! a full query over the frame through the Polars SQL context. Any other `op` is looked up
+//! among the transform plugins in the registry.
+
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use polars::sql::{sql_expr, SQLContext};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use sqlparser::ast::Statement;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::{Parser, ParserOptions};
+use std::collections::BTreeMap;
+
+use crate::plugins::config::{ConfigError, PluginConfig};
+use crate::plugins::registry::registry;
+use crate::plugins::Transform;
+
+pub mod wasm;
+
+/// Table name the frame is registered under for `sql` steps unless the step names one.
+pub const DEFAULT_SQL_TABLE: &str = "df";
+
+fn default_sql_table() -> String {
+    DEFAULT_SQL_TABLE.to_string()
+}
+
+/// A single transform step.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TransformStep {
+    /// Keep only these columns, in this order.
+    Select { columns: Vec<String> },
+    /// Rename columns, keyed by current name.
+    Rename { columns: BTreeMap<String, String> },
+    /// Cast columns to a type name (see `parse_dtype`). Non-strict casts turn failures into nulls.
+    Cast {
+        columns: BTreeMap<String, String>,
+        #[serde(default)]
+        strict: bool,
+    },
+    /// Keep rows for which the expression is true.
+    Filter { expr: String },
+    /// Add (or overwrite) a column computed from an expression.
+    Derive { column: String, expr: String },
+    /// Drop rows with a null in any of `columns`, or in any column when empty.
+    DropNulls {
+        #[serde(default)]
+        columns: Vec<String>,
+    },
+    Sort {
+        by: Vec<String>,
+        #[serde(default)]
+        descending: bool,
+    },
+    Limit { n: usize },
+    /// Keep one row per combination of `keys`: the first or last by `order_by`, or by current
+    /// row order when no ordering column is given. Surviving rows keep their relative order.
+    Dedupe {
+        keys: Vec<String>,
+        order_by: Option<String>,
+        #[serde(default)]
+        keep: DedupeKeep,
+    },
+    /// Run a SQL query over the frame, registered as table `table`; the result replaces the frame.
+    Sql {
+        query: String,
+        #[serde(default = "default_sql_table")]
+        table: String,
+    },
+    /// A step built by a transform plugin registered under a custom `op`.
+    #[serde(skip)]
+    Plugin(PluginStep),
+}
+
+/// A configured transform plugin.
+#[derive(Clone)]
+pub struct PluginStep {
+    pub op: String,
+    pub transform: Arc<dyn Transform>,
+}
+
+impl std::fmt::Debug for PluginStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginStep").field("op", &self.op).finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for PluginStep {
+    fn eq(&self, other: &Self) -> bool {
+        self.op == other.op && Arc::ptr_eq(&self.transform, &other.transform)
+    }
+}
+
+/// Which duplicate a `dedupe` step keeps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupeKeep {
+    First,
+    #[default]
+    Last,
+}
+
+/// Counts gathered while applying transforms, reported in the run metrics.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TransformStats {
+    /// Rows removed by `dedupe` steps; `None` when there are none.
+    pub rows_deduplicated: Option<usize>,
+}
+
+/// Name of the temporary column used to restore row order after deduplicating.
+const DEDUPE_ROW_INDEX: &str = "__orc_dedupe_row";
+
+/// Maps a type name used in `cast` steps to a Polars data type.
+pub fn parse_dtype(name: &str) -> Result<DataType> {
+    let dtype = match name.to_ascii_lowercase().as_str() {
+        "bool" | "boolean" => DataType::Boolean,
+        "int8" => DataType::Int8,
+        "int16" => DataType::Int16,
+        "int32" => DataType::Int32,
+        "int64" | "int" | "integer" => DataType::Int64,
+        "uint8" => DataType::UInt8,
+        "uint16" => DataType::UInt16,
+        "uint32" => DataType::UInt32,
+        "uint64" => DataType::UInt64,
+        "float32" => DataType::Float32,
+        "float64" | "float" | "double" => DataType::Float64,
+        "str" | "string" | "utf8" => DataType::String,
+        "date" => DataType::Date,
+        "datetime" | "timestamp" => DataType::Datetime(TimeUnit::Microseconds, None),
+        other => return Err(anyhow::anyhow!("Unsupported cast type: {}", other)),
+    };
+    Ok(dtype)
+}
+
+fn parse_expr(expr: &str) -> Result<Expr> {
+    sql_expr(expr).with_context(|| format!("Invalid expression '{}'", expr))
+}
+
+/// Checks that `query` is a single SELECT statement, parsed the way the Polars SQL context parses it.
+fn validate_query(query: &str) -> Result<()> {
+    let statements = Parser::new(&GenericDialect)
+        .with_options(ParserOptions {
+            trailing_commas: true,
+            ..Default::default()
+        })
+        .try_with_sql(query)
+        .and_then(|mut parser| parser.parse_statements())
+        .with_context(|| format!("Invalid SQL query '{}'", query))?;
+    match statements.as_slice() {
+        [Statement::Query(_)] => Ok(()),
+        [_] => Err(anyhow::anyhow!("SQL transform must be a SELECT query")),
+        _ => Err(anyhow::anyhow!("SQL transform must contain exactly one statement")),
+    }
+}
+
+impl TransformStep {
+    /// Checks the step without looking at any data: types and expressions must parse.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            TransformStep::Select { columns } if columns.is_empty() => {
+                Err(anyhow::anyhow!("select requires at least one column"))
+            }
+            TransformStep::Sort { by, .. } if by.is_empty() => Err(anyhow::anyhow!("sort requires at least one column")),
+            TransformStep::Dedupe { keys, .. } if keys.is_empty() => Err(anyhow::anyhow!("dedupe requires at least one key column")),
+            TransformStep::Cast { columns, .. } => columns.values().try_for_each(|t| parse_dtype(t).map(|_| ())),
+            TransformStep::Filter { expr } | TransformStep::Derive { expr, .. } => parse_expr(expr).map(|_| ()),
+            TransformStep::Sql { table, .. } if table.is_empty() => Err(anyhow::anyhow!("sql table name must not be empty")),
+            TransformStep::Sql { query, .. } => validate_query(query),
+            _ => Ok(()),
+        }
+    }
+
+    fn apply(&self, lf: LazyFrame) -> Result<LazyFrame> {
+        let lf = match self {
+            TransformStep::Select { columns } => lf.select(columns.iter().map(|c| col(c)).collect::<Vec<_>>()),
+            TransformStep::Rename { columns } => lf.rename(columns.keys(), columns.values()),
+            TransformStep::Cast { columns, strict } => {
+                let casts = columns
+                    .iter()
+                    .map(|(name, type_name)| {
+                        let dtype = parse_dtype(type_name)?;
+                        Ok(if *strict { col(name).strict_cast(dtype) } else { col(name).cast(dtype) })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                lf.with_columns(casts)
+            }
+            TransformStep::Filter { expr } => lf.filter(parse_expr(expr)?),
+            TransformStep::Derive { column, expr } => lf.with_column(parse_expr(expr)?.alias(column)),
+            TransformStep::DropNulls { columns } if columns.is_empty() => lf.drop_nulls(None),
+            TransformStep::DropNulls { columns } => lf.drop_nulls(Some(columns.iter().map(|c| col(c)).collect())),
+            TransformStep::Sort { by, descending } => lf.sort(
+                by.iter().map(|c| c.as_str()).collect::<Vec<_>>(),
+                SortMultipleOptions::default().with_order_descending(*descending),
+            ),
+            TransformStep::Limit { n } => lf.limit(*n as IdxSize),
+            TransformStep::Dedupe { keys, order_by, keep } => {
+                let strategy = match keep {
+                    DedupeKeep::First => UniqueKeepStrategy::First,
+                    DedupeKeep::Last => UniqueKeepStrategy::Last,
+                };
+                match order_by {
+                    None => lf.unique_stable(Some(keys.clone()), strategy),
+                    Some(order_by) => lf
+                        .with_row_index(DEDUPE_ROW_INDEX, None)
+                        .sort([order_by.as_str()], SortMultipleOptions::default().with_maintain_order(true))
+                        .unique_stable(Some(keys.clone()), strategy)
+                        .sort([DEDUPE_ROW_INDEX], SortMultipleOptions::default())
+                        .drop([DEDUPE_ROW_INDEX]),
+                }
+            }
+            TransformStep::Sql { query, table } => {
+                let mut ctx = SQLContext::new();
+                ctx.register(table, lf);
+                ctx.execute(query).with_context(|| format!("Failed to run SQL query '{}'", query))?
+            }
+            TransformStep::Plugin(step) => step.transform.apply(lf).with_context(|| format!("Transform plugin '{}' failed", step.op))?,
+        };
+        Ok(lf)
+    }
+}
+
+impl PluginConfig for TransformStep {
+    /// Each built-in step registers its own schema.
+    fn config_schema() -> Value {
+        serde_json::json!({ "type": "object" })
+    }
+
+    fn validate(&self) -> std::result::Result<(), ConfigError> {
+        let field = match self {
+            TransformStep::Filter { .. } | TransformStep::Derive { .. } => "expr",
+            TransformStep::Sort { .. } => "by",
+            TransformStep::Limit { .. } => "n",
+            TransformStep::Dedupe { .. } => "keys",
+            TransformStep::Sql { table, .. } if table.is_empty() => "table",
+            TransformStep::Sql { .. } => "query",
+            _ => "columns",
+        };
+        TransformStep::validate(self).map_err(|e| ConfigError::new(field, format!("{:#}", e)))
+    }
+}
+
+/// Parses and validates a task's `transform` config, which must be a list of steps.
+pub fn parse_transforms(config: &Value) -> Result<Vec<TransformStep>> {
+    let items = config.as_array().context("transform must be a list of steps")?;
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| registry().transform(item).with_context(|| format!("Invalid transform step {}", i + 1)))
+        .collect()
+}
+
+/// Applies the steps in order and materializes the result. The frame is also materialized
+/// around each `dedupe` step to count the rows it drops.
+pub fn apply_transforms(df: DataFrame, steps: &[TransformStep]) -> Result<(DataFrame, TransformStats)> {
+    let mut stats = TransformStats::default();
+    let mut lf = df.lazy();
+    for (i, step) in steps.iter().enumerate() {
+        let context = || format!("Failed to apply transform step {}", i + 1);
+        if let TransformStep::Dedupe { .. } = step {
+            let before = lf.collect().with_context(context)?;
+            let after = step.apply(before.clone().lazy()).and_then(|lf| Ok(lf.collect()?)).with_context(context)?;
+            *stats.rows_deduplicated.get_or_insert(0) += before.height() - after.height();
+            lf = after.lazy();
+        } else {
+            lf = step.apply(lf).with_context(context)?;
+        }
+    }
+    let df = lf.collect().context("Failed to evaluate transforms")?;
+    Ok((df, stats))
+}
+
+/// Names in the input frame of the given output columns, following `rename` steps back.
+/// Columns added by other steps keep their own name.
+pub fn source_columns(steps: &[TransformStep], columns: &[String]) -> Vec<String> {
+    steps.iter().rev().fold(columns.to_vec(), |names, step| match step {
+        TransformStep::Rename { columns: renames } => names
+            .into_iter()
+            .map(|name| renames.iter().find(|(_, new)| **new == name).map_or(name, |(old, _)| old.clone()))
+            .collect(),
+        _ => names,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_applies_steps_in_order() {
+        let df = df!(
+            "id" => &[Some(3i64), Some(1), Some(2), None],
+            "price" => &["2.5", "4", "1", "3"],
+            "qty" => &[2i64, 1, 5, 1]
+        )
+        .unwrap();
+        let steps = parse_transforms(&json!([
+            { "op": "drop_nulls", "columns": ["id"] },
+            { "op": "cast", "columns": { "price": "float64" } },
+            { "op": "derive", "column": "total", "expr": "price * qty" },
+            { "op": "filter", "expr": "total > 4" },
+            { "op": "rename", "columns": { "id": "order_id" } },
+            { "op": "sort", "by": ["order_id"] },
+            { "op": "select", "columns": ["order_id", "total"] },
+            { "op": "limit", "n": 1 }
+        ]))
+        .unwrap();
+
+        let (out, _) = apply_transforms(df, &steps).unwrap();
+
+        let expected = df!("order_id" => &[2i64], "total" => &[5.0f64]).unwrap();
+        assert!(out.equals(&expected), "unexpected output: {:?}", out);
+    }
+
+    #[test]
+    fn test_source_columns_follow_renames_back() {
+        let steps = parse_transforms(&json!([
+            { "op": "rename", "columns": { "name": "customer" } },
+            { "op": "derive", "column": "email", "expr": "customer" },
+            { "op": "rename", "columns": { "customer": "client" } }
+        ]))
+        .unwrap();
+
+        let columns = ["client".to_string(), "email".to_string(), "id".to_string()];
+        assert_eq!(source_columns(&steps, &columns), vec!["name", "email", "id"]);
+    }
+
+    #[test]
+    fn test_sql_step_queries_named_table() {
+        let df = df!("city" => &["Oslo", "Rome", "Oslo"], "amount" => &[10i64, 5, 7]).unwrap();
+        let steps = parse_transforms(&json!([
+            { "op": "sql", "table": "orders", "query": "SELECT city, SUM(amount) AS total FROM orders GROUP BY city ORDER BY city" },
+            { "op": "sql", "query": "SELECT * FROM df WHERE total > 6" }
+        ]))
+        .unwrap();
+
+        let (out, _) = apply_transforms(df, &steps).unwrap();
+
+        let expected = df!("city" => &["Oslo"], "total" => &[17i64]).unwrap();
+        assert!(out.equals(&expected), "unexpected output: {:?}", out);
+    }
+
+    #[test]
+    fn test_dedupe_keeps_latest_and_counts_dropped_rows() {
+        let df = df!(
+            "id" => &[1i64, 2, 1, 3, 2],
+            "updated" => &[5i64, 1, 3, 2, 4],
+            "value" => &["a-new", "b-old", "a-old", "c", "b-new"]
+        )
+        .unwrap();
+        let steps = parse_transforms(&json!([
+            { "op": "dedupe", "keys": ["id"], "order_by": "updated", "keep": "last" }
+        ]))
+        .unwrap();
+
+        let (out, stats) = apply_transforms(df.clone(), &steps).unwrap();
+
+        let expected = df!("id" => &[1i64, 3, 2], "updated" => &[5i64, 2, 4], "value" => &["a-new", "c", "b-new"]).unwrap();
+        assert!(out.equals(&expected), "unexpected output: {:?}", out);
+        assert_eq!(stats.rows_deduplicated, Some(2));
+
+        let first = parse_transforms(&json!([{ "op": "dedupe", "keys": ["id"], "keep": "first" }])).unwrap();
+        let (out, _) = apply_transforms(df, &first).unwrap();
+        assert_eq!(out.column("value").unwrap().str().unwrap().get(0), Some("a-new"));
+        assert_eq!(out.column("value").unwrap().str().unwrap().get(1), Some("b-old"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_steps() {
+        let err = parse_transforms(&json!([{ "op": "limit", "n": 1 }, { "op": "cast", "columns": { "a": "money" } }]))
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("step 2"));
+        assert!(parse_transforms(&json!([{ "op": "filter", "expr": "a >" }])).is_err());
+        assert!(parse_transforms(&json!([{ "op": "explode" }])).is_err());
+        assert!(parse_transforms(&json!([{ "op": "sql", "query": "SELEC * FROM df" }])).is_err());
+        assert!(parse_transforms(&json!([{ "op": "sql", "query": "DROP TABLE df" }])).is_err());
+        assert!(parse_transforms(&json!({ "op": "limit", "n": 1 })).is_err());
+    }
+}
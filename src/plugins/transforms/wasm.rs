@@ -0,0 +1,235 @@
+//! Transform steps run as sandboxed WebAssembly modules.
+//!
+//! A `wasm` step lets a team ship a custom transform without running native code on the host.
+//! Modules are loaded by file name from the plugin directory (`PLUGIN_DIR`, `plugins` by
+//! default) and may be binary `.wasm` or text `.wat` files. A module gets no imports, so it
+//! can only compute on what it is given, within the memory and fuel limits of its step.
+//!
+//! A module must export:
+//! * `memory`: its linear memory.
+//! * `alloc(len: i32) -> i32`: reserves `len` bytes and returns their offset.
+//! * `transform(data_ptr: i32, data_len: i32, config_ptr: i32, config_len: i32) -> i64`:
+//!   transforms one batch. The input is an Arrow IPC stream; the config is the step's `config`
+//!   object as JSON. Returns the offset of the output Arrow IPC stream in the upper 32 bits and
+//!   its length in the lower 32 bits.
+//!
+//! The frame is passed in batches of `batch_size` rows, one `transform` call per batch, and the
+//! output batches are concatenated. All batches share one instance and one fuel budget.
+
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use serde_json::Value;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use wasmtime::{Config, Engine, Instance, Memory, Module, ResourceLimiter, Store, TypedFunc};
+
+use crate::plugins::Transform;
+
+/// Environment variable naming the directory WASM modules are loaded from.
+pub const PLUGIN_DIR_ENV: &str = "PLUGIN_DIR";
+pub const DEFAULT_PLUGIN_DIR: &str = "plugins";
+
+static ENGINE: LazyLock<Engine> = LazyLock::new(|| {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config).expect("Failed to create the WebAssembly engine")
+});
+
+pub fn plugin_dir() -> PathBuf {
+    PathBuf::from(std::env::var(PLUGIN_DIR_ENV).unwrap_or_else(|_| DEFAULT_PLUGIN_DIR.to_string()))
+}
+
+/// Path of a module in the plugin directory. Names can't leave the directory.
+pub fn module_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        anyhow::bail!("module must be a file name in the plugin directory, got '{}'", name);
+    }
+    let path = plugin_dir().join(name);
+    if !path.is_file() {
+        anyhow::bail!("no module '{}' in plugin directory '{}'", name, plugin_dir().display());
+    }
+    Ok(path)
+}
+
+/// Resources a module may use while transforming one frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasmLimits {
+    pub max_memory_bytes: usize,
+    /// Fuel roughly counts executed instructions; the step fails when it runs out.
+    pub fuel: u64,
+}
+
+/// Caps memory growth, remembering the limit for the error message.
+struct MemoryLimiter {
+    max_memory_bytes: usize,
+}
+
+impl ResourceLimiter for MemoryLimiter {
+    fn memory_growing(&mut self, _current: usize, desired: usize, maximum: Option<usize>) -> Result<bool> {
+        if desired > self.max_memory_bytes {
+            anyhow::bail!("module exceeded its memory limit of {} bytes", self.max_memory_bytes);
+        }
+        Ok(maximum.is_none_or(|max| desired <= max))
+    }
+
+    fn table_growing(&mut self, _current: usize, desired: usize, maximum: Option<usize>) -> Result<bool> {
+        Ok(maximum.is_none_or(|max| desired <= max))
+    }
+}
+
+pub struct WasmTransform {
+    name: String,
+    module: Module,
+    config: Vec<u8>,
+    limits: WasmLimits,
+    batch_size: usize,
+}
+
+impl WasmTransform {
+    /// Compiles a module from its bytes (binary or text format).
+    pub fn new(name: &str, bytes: &[u8], config: &Value, limits: WasmLimits, batch_size: usize) -> Result<Self> {
+        let module = Module::new(&ENGINE, bytes).with_context(|| format!("Failed to compile WASM module '{}'", name))?;
+        Ok(Self {
+            name: name.to_string(),
+            module,
+            config: serde_json::to_vec(config)?,
+            limits,
+            batch_size: batch_size.max(1),
+        })
+    }
+
+    /// Compiles the named module from the plugin directory.
+    pub fn load(name: &str, config: &Value, limits: WasmLimits, batch_size: usize) -> Result<Self> {
+        let path = module_path(name)?;
+        let bytes = std::fs::read(&path).with_context(|| format!("Failed to read WASM module '{}'", path.display()))?;
+        Self::new(name, &bytes, config, limits, batch_size)
+    }
+
+    fn run(&self, df: DataFrame) -> Result<DataFrame> {
+        let fail = |e: anyhow::Error| anyhow::anyhow!("WASM module '{}' failed: {}", self.name, e.root_cause());
+
+        let mut store = Store::new(&ENGINE, MemoryLimiter { max_memory_bytes: self.limits.max_memory_bytes });
+        store.limiter(|limiter| limiter);
+        store.set_fuel(self.limits.fuel)?;
+        let instance = Instance::new(&mut store, &self.module, &[]).map_err(fail)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .with_context(|| format!("WASM module '{}' does not export `memory`", self.name))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc").map_err(fail)?;
+        let transform = instance
+            .get_typed_func::<(i32, i32, i32, i32), i64>(&mut store, "transform")
+            .map_err(fail)?;
+
+        let (config_ptr, config_len) = write_bytes(&mut store, memory, &alloc, &self.config).map_err(fail)?;
+        let mut output: Option<DataFrame> = None;
+        let mut offset = 0;
+        // An empty frame still makes one call, so the output has the module's schema.
+        while offset < df.height() || output.is_none() {
+            let mut batch = df.slice(offset as i64, self.batch_size);
+            offset += self.batch_size;
+
+            let mut input = Vec::new();
+            IpcStreamWriter::new(&mut input).finish(&mut batch)?;
+            let (ptr, len) = write_bytes(&mut store, memory, &alloc, &input).map_err(fail)?;
+            let packed = transform.call(&mut store, (ptr, len, config_ptr, config_len)).map_err(fail)? as u64;
+            let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+            let bytes = memory
+                .data(&store)
+                .get(out_ptr..out_ptr + out_len)
+                .with_context(|| format!("WASM module '{}' returned output outside its memory", self.name))?;
+            let batch = IpcStreamReader::new(Cursor::new(bytes))
+                .finish()
+                .with_context(|| format!("WASM module '{}' returned an invalid Arrow IPC stream", self.name))?;
+            match output.as_mut() {
+                None => output = Some(batch),
+                Some(df) => {
+                    df.vstack_mut(&batch)
+                        .with_context(|| format!("WASM module '{}' returned batches with different schemas", self.name))?;
+                }
+            }
+        }
+        Ok(output.unwrap_or_default())
+    }
+}
+
+/// Copies `bytes` into memory reserved with the module's `alloc`.
+fn write_bytes(store: &mut Store<MemoryLimiter>, memory: Memory, alloc: &TypedFunc<i32, i32>, bytes: &[u8]) -> Result<(i32, i32)> {
+    let len = i32::try_from(bytes.len()).context("input is larger than 2 GiB")?;
+    let ptr = alloc.call(&mut *store, len)?;
+    memory
+        .write(&mut *store, ptr as u32 as usize, bytes)
+        .context("alloc returned an offset outside the module's memory")?;
+    Ok((ptr, len))
+}
+
+impl Transform for WasmTransform {
+    fn apply(&self, lf: LazyFrame) -> Result<LazyFrame> {
+        Ok(self.run(lf.collect()?)?.lazy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Returns each batch unchanged. `alloc` bumps a pointer, growing memory as needed.
+    const IDENTITY: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (if (i32.gt_u (global.get $next) (i32.mul (memory.size) (i32.const 65536)))
+              (then (drop (memory.grow (i32.add (i32.div_u (local.get $len) (i32.const 65536)) (i32.const 1))))))
+            (local.get $ptr))
+          (func (export "transform") (param $ptr i32) (param $len i32) (param i32) (param i32) (result i64)
+            (i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32)) (i64.extend_i32_u (local.get $len)))))
+    "#;
+
+    const SPIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 0))
+          (func (export "transform") (param i32 i32 i32 i32) (result i64) (loop $l (br $l)) (i64.const 0)))
+    "#;
+
+    const LIMITS: WasmLimits = WasmLimits { max_memory_bytes: 16 << 20, fuel: 100_000_000 };
+
+    #[test]
+    fn test_transforms_frame_in_batches() {
+        let df = df!("id" => (0..25i64).collect::<Vec<_>>(), "name" => (0..25).map(|i| format!("n{}", i)).collect::<Vec<_>>()).unwrap();
+        let step = WasmTransform::new("identity", IDENTITY.as_bytes(), &json!({}), LIMITS, 10).unwrap();
+
+        let out = step.apply(df.clone().lazy()).unwrap().collect().unwrap();
+        assert!(out.equals(&df));
+
+        let empty = step.apply(df.head(Some(0)).lazy()).unwrap().collect().unwrap();
+        assert_eq!((empty.height(), empty.width()), (0, 2));
+    }
+
+    #[test]
+    fn test_enforces_fuel_and_memory_limits() {
+        let df = df!("id" => [1i64, 2, 3]).unwrap();
+
+        let spin = WasmTransform::new("spin", SPIN.as_bytes(), &json!({}), LIMITS, 10).unwrap();
+        let err = spin.run(df.clone()).unwrap_err().to_string();
+        assert!(err.contains("fuel"), "{}", err);
+
+        // One 64 KiB page is allowed, but the batch alone is larger.
+        let small = WasmLimits { max_memory_bytes: 1 << 16, ..LIMITS };
+        let identity = WasmTransform::new("identity", IDENTITY.as_bytes(), &json!({}), small, 100_000).unwrap();
+        let large = df!("id" => (0..20_000i64).collect::<Vec<_>>()).unwrap();
+        let err = identity.run(large).unwrap_err().to_string();
+        assert!(err.contains("memory limit"), "{}", err);
+    }
+
+    #[test]
+    fn test_module_names_stay_in_plugin_directory() {
+        assert!(module_path("../secrets.wasm").unwrap_err().to_string().contains("file name"));
+        assert!(module_path("missing.wasm").unwrap_err().to_string().contains("no module"));
+    }
+}
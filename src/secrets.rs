@@ -0,0 +1,250 @@
+//! Secrets referenced from task configs.
+//!
+//! Credentials don't belong in task configs, which are stored in plain text and returned by the
+//! API. Instead, any string in a config may contain `${secret:NAME}` references, which the worker
+//! replaces with the secret's value when the task runs. The resolved config is only held in
+//! memory for that attempt, and the values are redacted from its logs, metrics and errors.
+//!
+//! A secret is looked up, in order, in:
+//! 1. the environment variable `ORC_SECRET_NAME`. Only prefixed variables are read, so a
+//!    config can't reach the orchestrator's own settings, such as `SECRETS_KEY` or `DATABASE_URL`;
+//! 2. the YAML or JSON file of `NAME: value` pairs named by `SECRETS_FILE`;
+//! 3. the `secrets` table, managed through the `/secrets` API, where values are encrypted with
+//!    AES-256-GCM under the base64-encoded 32-byte key in `SECRETS_KEY`.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{Context, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::plugins::config::ConfigError;
+use crate::plugins::RunLog;
+use crate::state::db::{Db, StoredSecret};
+
+/// Environment variable naming the secrets file.
+pub const SECRETS_FILE_ENV: &str = "SECRETS_FILE";
+/// Environment variable holding the key that encrypts the `secrets` table.
+pub const SECRETS_KEY_ENV: &str = "SECRETS_KEY";
+/// Prefix of the environment variables secrets are read from.
+pub const SECRET_ENV_PREFIX: &str = "ORC_SECRET_";
+
+const REFERENCE_START: &str = "${secret:";
+const NONCE_LEN: usize = 12;
+
+/// Whether `name` can be used as a secret name: letters, digits, `_`, `-` and `.`.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Names referenced by a string, or a description of the first malformed reference.
+fn references(text: &str) -> Result<Vec<&str>, String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(REFERENCE_START) {
+        let after = &rest[start + REFERENCE_START.len()..];
+        let Some(end) = after.find('}') else {
+            return Err(format!("unterminated secret reference in '{}'", text));
+        };
+        let name = &after[..end];
+        if !is_valid_name(name) {
+            return Err(format!("invalid secret name '{}'", name));
+        }
+        names.push(name);
+        rest = &after[end + 1..];
+    }
+    Ok(names)
+}
+
+/// Checks the syntax of every secret reference in a config. Whether the secrets exist is only
+/// known when the task runs.
+pub fn check_references(config: &Value) -> Result<(), ConfigError> {
+    match config {
+        Value::String(text) => references(text).map(|_| ()).map_err(|e| ConfigError::new("", e)),
+        Value::Array(items) => {
+            let mut errors = ConfigError::default();
+            for (i, item) in items.iter().enumerate() {
+                errors.merge(&format!("[{}]", i), check_references(item));
+            }
+            errors.into_result()
+        }
+        Value::Object(fields) => {
+            let mut errors = ConfigError::default();
+            for (key, value) in fields {
+                errors.merge(key, check_references(value));
+            }
+            errors.into_result()
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Looks up secrets from the environment, the secrets file and the `secrets` table.
+pub struct SecretStore {
+    db: Db,
+    file: Option<PathBuf>,
+    /// Cipher for the `secrets` table, keyed by `SECRETS_KEY`.
+    cipher: Option<Aes256Gcm>,
+}
+
+impl SecretStore {
+    /// Creates a store configured by `SECRETS_FILE` and `SECRETS_KEY`.
+    pub fn from_env(db: Db) -> Result<Self> {
+        let cipher = match std::env::var(SECRETS_KEY_ENV) {
+            Ok(encoded) => Some(cipher(&encoded)?),
+            Err(_) => None,
+        };
+        Ok(Self { db, file: std::env::var_os(SECRETS_FILE_ENV).map(PathBuf::from), cipher })
+    }
+
+    /// Whether secrets can be stored, i.e. `SECRETS_KEY` is set.
+    pub fn can_store(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// The value of a secret. Fails if no source has it.
+    pub async fn get(&self, name: &str) -> Result<String> {
+        if let Ok(value) = std::env::var(format!("{}{}", SECRET_ENV_PREFIX, name)) {
+            return Ok(value);
+        }
+        if let Some(path) = &self.file {
+            let contents = std::fs::read_to_string(path).with_context(|| format!("Failed to read secrets file '{}'", path.display()))?;
+            let secrets: BTreeMap<String, String> =
+                serde_yaml::from_str(&contents).with_context(|| format!("Failed to parse secrets file '{}'", path.display()))?;
+            if let Some(value) = secrets.get(name) {
+                return Ok(value.clone());
+            }
+        }
+        match self.db.get_secret(name).await? {
+            Some(stored) => self.decrypt(&stored),
+            None => anyhow::bail!("Secret '{}' is not set", name),
+        }
+    }
+
+    /// Replaces the secret references in a config with their values, registering each value
+    /// with the run's log so it is redacted from the attempt's output.
+    pub async fn resolve(&self, config: &Value, run_log: &RunLog) -> Result<Value> {
+        Ok(match config {
+            Value::String(text) => {
+                let names = references(text).map_err(anyhow::Error::msg)?;
+                let mut resolved = text.clone();
+                for name in names {
+                    let value = self.get(name).await?;
+                    run_log.add_secret(&value);
+                    resolved = resolved.replace(&format!("{}{}}}", REFERENCE_START, name), &value);
+                }
+                Value::String(resolved)
+            }
+            Value::Array(items) => {
+                let mut resolved = Vec::with_capacity(items.len());
+                for item in items {
+                    resolved.push(Box::pin(self.resolve(item, run_log)).await?);
+                }
+                Value::Array(resolved)
+            }
+            Value::Object(fields) => {
+                let mut resolved = serde_json::Map::with_capacity(fields.len());
+                for (key, value) in fields {
+                    resolved.insert(key.clone(), Box::pin(self.resolve(value, run_log)).await?);
+                }
+                Value::Object(resolved)
+            }
+            other => other.clone(),
+        })
+    }
+
+    /// Encrypts and stores a secret in the `secrets` table.
+    pub async fn set(&self, name: &str, value: &str) -> Result<StoredSecret> {
+        let cipher = self.cipher.as_ref().with_context(|| format!("{} is not set, so secrets can't be stored", SECRETS_KEY_ENV))?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, value.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt secret '{}'", name))?;
+        let sealed = [&nonce[..], &ciphertext].concat();
+        self.db.upsert_secret(name, &BASE64_STANDARD.encode(sealed)).await
+    }
+
+    fn decrypt(&self, stored: &StoredSecret) -> Result<String> {
+        let cipher = self
+            .cipher
+            .as_ref()
+            .with_context(|| format!("{} is not set, so secret '{}' can't be decrypted", SECRETS_KEY_ENV, stored.name))?;
+        let sealed = BASE64_STANDARD.decode(&stored.ciphertext).with_context(|| format!("Secret '{}' is corrupt", stored.name))?;
+        if sealed.len() < NONCE_LEN {
+            anyhow::bail!("Secret '{}' is corrupt", stored.name);
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into()?;
+        let plaintext = cipher
+            .decrypt(&Nonce::from(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt secret '{}'; was {} changed?", stored.name, SECRETS_KEY_ENV))?;
+        String::from_utf8(plaintext).with_context(|| format!("Secret '{}' is not valid UTF-8", stored.name))
+    }
+}
+
+fn cipher(encoded: &str) -> Result<Aes256Gcm> {
+    let bytes = BASE64_STANDARD
+        .decode(encoded.trim())
+        .with_context(|| format!("{} must be base64", SECRETS_KEY_ENV))?;
+    if bytes.len() != 32 {
+        anyhow::bail!("{} must encode 32 bytes, got {}", SECRETS_KEY_ENV, bytes.len());
+    }
+    Ok(Aes256Gcm::new_from_slice(&bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn store(file: Option<PathBuf>) -> SecretStore {
+        let db = Db::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        SecretStore { db, file, cipher: Some(cipher(&BASE64_STANDARD.encode([7u8; 32])).unwrap()) }
+    }
+
+    #[tokio::test]
+    async fn test_resolves_references_from_each_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("secrets.yaml");
+        std::fs::write(&file, "ORC_TEST_FILE_SECRET: from-file\n").unwrap();
+        std::env::set_var("ORC_SECRET_TEST_ENV_SECRET", "from-env");
+        std::env::set_var("ORC_TEST_UNPREFIXED", "from-env");
+        let store = store(Some(file)).await;
+        let stored = store.set("warehouse.password", "p@ss").await.unwrap();
+        assert_ne!(stored.ciphertext, "p@ss");
+
+        let run_log = RunLog::default();
+        let config = json!({
+            "url": "postgres://etl:${secret:warehouse.password}@db/prod",
+            "headers": [{ "token": "${secret:TEST_ENV_SECRET}" }, "${secret:ORC_TEST_FILE_SECRET}"],
+            "port": 5432
+        });
+        let resolved = store.resolve(&config, &run_log).await.unwrap();
+        assert_eq!(
+            resolved,
+            json!({ "url": "postgres://etl:p@ss@db/prod", "headers": [{ "token": "from-env" }, "from-file"], "port": 5432 })
+        );
+        assert_eq!(run_log.redact("token from-env, password p@ss"), "token ***, password ***");
+
+        let err = store.resolve(&json!("${secret:ORC_TEST_MISSING}"), &run_log).await.unwrap_err();
+        assert_eq!(err.to_string(), "Secret 'ORC_TEST_MISSING' is not set");
+        // Environment variables without the prefix aren't secrets.
+        let err = store.get("ORC_TEST_UNPREFIXED").await.unwrap_err();
+        assert_eq!(err.to_string(), "Secret 'ORC_TEST_UNPREFIXED' is not set");
+
+        // Without the key, stored secrets can't be read.
+        let keyless = SecretStore { cipher: None, db: store.db.clone(), file: None };
+        assert!(keyless.get("warehouse.password").await.unwrap_err().to_string().contains(SECRETS_KEY_ENV));
+    }
+
+    #[test]
+    fn test_rejects_malformed_references() {
+        let errors = check_references(&json!({ "a": ["${secret:ok} ${secret:bad name}"], "b": "${secret:open" })).unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["a[0]", "b"]);
+        assert!(check_references(&json!({ "literal": "${not_a_secret}" })).is_ok());
+    }
+}
@@ -14,7 +14,7 @@
 //! Provides database access and defines data models for the application.
 //! 
 //! This module handles database connection pooling, migrations, and CRUD operations
-//! for `JobDefinition`, `TaskDefinition`, and `JobRun` entities.
+//! for `JobDefinition`, `TaskDefinition`, `JobRun` and `JobResult` entities.
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
@@ -26,6 +26,7 @@ use uuid::Uuid;
 // --- Data Structures ---
 
 use crate::models::user::User;
+use crate::plugins::LogLine;
 
 #[derive(Debug, FromRow, Serialize, Clone)]
 pub struct JobDefinition {
@@ -34,6 +35,8 @@ pub struct JobDefinition {
     pub description: Option<String>,
     pub schedule: String,
     pub is_active: bool,
+    /// Parameters the job's runs accept (see `orchestrator::params::ParamSchema`).
+    pub params_schema: Option<Value>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -45,10 +48,30 @@ pub struct TaskDefinition {
     pub task_order: i32,
     pub extractor_config: Value,
     pub loader_config: Value,
+    /// Optional list of transform steps applied between extraction and loading.
+    pub transform_config: Option<Value>,
+    /// Optional list of data quality checks evaluated before loading.
+    pub checks_config: Option<Value>,
+    /// Whether removed or retyped columns since the previous successful run fail the task.
+    pub fail_on_breaking_drift: bool,
+    /// Optional list of column masking policies applied before loading.
+    pub masking_config: Option<Value>,
+    /// Program run by a command task, which has no extractor or loader (both are JSON null).
+    pub command_config: Option<Value>,
+    /// Statements run by a SQL task, which likewise has no extractor or loader.
+    pub sql_config: Option<Value>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl TaskDefinition {
+    /// Every config the task has, e.g. to resolve references in all of them.
+    pub fn configs_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        let optional = [&mut self.transform_config, &mut self.checks_config, &mut self.masking_config, &mut self.command_config, &mut self.sql_config];
+        [&mut self.extractor_config, &mut self.loader_config].into_iter().chain(optional.into_iter().flatten())
+    }
+}
+
 #[derive(Debug, FromRow, Serialize, Clone)]
 pub struct JobRun {
     pub run_id: Uuid,
@@ -58,10 +81,155 @@ pub struct JobRun {
     pub started_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
     pub error_message: Option<String>,
+    /// Parameters of the run, checked against the job's schema and completed with defaults.
+    pub params: Option<Value>,
+    /// Per-task loader outcomes recorded by the worker (see `worker::metrics::RunMetrics`).
+    pub metrics: Option<Value>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Values for a new `task_definitions` row.
+#[derive(Debug, Clone)]
+pub struct NewTaskDefinition {
+    pub job_id: Uuid,
+    pub task_order: i32,
+    pub extractor_config: Value,
+    pub loader_config: Value,
+    pub transform_config: Option<Value>,
+    pub checks_config: Option<Value>,
+    pub fail_on_breaking_drift: bool,
+    pub masking_config: Option<Value>,
+    pub command_config: Option<Value>,
+    pub sql_config: Option<Value>,
+}
+
+/// Lineage record of what a loader wrote during a job run.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct JobResult {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub run_id: Option<Uuid>,
+    pub task_order: Option<i32>,
+    /// Position of the loader in the task's `loader_config`.
+    pub loader_index: Option<i32>,
+    pub loader_type: Option<String>,
+    pub warehouse_table: String,
+    pub file_path: Option<String>,
+    pub row_count: i64,
+    pub bytes_written: Option<i64>,
+    pub duration_ms: i64,
+    pub schema_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Values for a new `job_results` row.
+#[derive(Debug, Clone)]
+pub struct NewJobResult {
+    pub job_id: Uuid,
+    pub run_id: Uuid,
+    pub task_order: i32,
+    pub loader_index: i32,
+    pub loader_type: String,
+    pub warehouse_table: String,
+    pub file_path: Option<String>,
+    pub row_count: i64,
+    pub bytes_written: Option<i64>,
+    pub duration_ms: i64,
+    pub schema_hash: String,
+}
+
+/// Outcome of a data quality check evaluated during a job run.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct CheckResult {
+    pub id: Uuid,
+    pub run_id: Uuid,
+    pub job_id: Uuid,
+    pub task_order: i32,
+    pub check_order: i32,
+    pub check_name: String,
+    pub column_name: Option<String>,
+    pub severity: String,
+    pub passed: bool,
+    pub failing_rows: Option<i64>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Values for a new `check_results` row.
+#[derive(Debug, Clone)]
+pub struct NewCheckResult {
+    pub run_id: Uuid,
+    pub job_id: Uuid,
+    pub task_order: i32,
+    pub check_order: i32,
+    pub check_name: String,
+    pub column_name: Option<String>,
+    pub severity: String,
+    pub passed: bool,
+    pub failing_rows: Option<i64>,
+    pub message: String,
+}
+
+/// Schema of a task's extracted data in one run.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct SchemaSnapshot {
+    pub id: Uuid,
+    pub run_id: Uuid,
+    pub job_id: Uuid,
+    pub task_order: i32,
+    /// `[{"name": ..., "dtype": ...}]` in column order.
+    pub columns: Value,
+    pub schema_hash: String,
+    /// Difference from the previous successful run's snapshot, if any.
+    pub drift: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Values for a new `schema_snapshots` row.
+#[derive(Debug, Clone)]
+pub struct NewSchemaSnapshot {
+    pub run_id: Uuid,
+    pub job_id: Uuid,
+    pub task_order: i32,
+    pub columns: Value,
+    pub schema_hash: String,
+    pub drift: Option<Value>,
+}
+
+/// A secret stored encrypted in the `secrets` table. The ciphertext is never serialized.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct StoredSecret {
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub ciphertext: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A named connection, whose options are the defaults of every config that references it.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct ConnectionDefinition {
+    pub name: String,
+    pub connection_type: String,
+    pub options: Value,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// A line of task output captured during a run.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct RunLogLine {
+    pub id: Uuid,
+    pub run_id: Uuid,
+    pub task_order: i32,
+    /// Position of the line within the attempt that produced it.
+    pub line_no: i32,
+    pub stream: String,
+    pub message: String,
+    pub logged_at: DateTime<Utc>,
+}
+
 // --- Database Connection ---
 
 #[derive(Clone)]
@@ -110,6 +278,7 @@ CREATE TABLE IF NOT EXISTS job_definitions (
     description TEXT,
     schedule TEXT NOT NULL,
     is_active BOOLEAN NOT NULL DEFAULT 1,
+    params_schema TEXT,
     created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
 );
@@ -120,6 +289,12 @@ CREATE TABLE IF NOT EXISTS task_definitions (
     task_order INT NOT NULL,
     extractor_config TEXT NOT NULL,
     loader_config TEXT NOT NULL,
+    transform_config TEXT,
+    checks_config TEXT,
+    fail_on_breaking_drift BOOLEAN NOT NULL DEFAULT 0,
+    masking_config TEXT,
+    command_config TEXT,
+    sql_config TEXT,
     created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
     UNIQUE (job_id, task_order)
@@ -133,6 +308,8 @@ CREATE TABLE IF NOT EXISTS job_runs (
     started_at DATETIME,
     finished_at DATETIME,
     error_message TEXT,
+    params TEXT,
+    metrics TEXT,
     updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
     created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
 );
@@ -148,13 +325,78 @@ CREATE TABLE IF NOT EXISTS users (
 CREATE TABLE IF NOT EXISTS job_results (
     id TEXT PRIMARY KEY,
     job_id TEXT NOT NULL REFERENCES job_definitions(job_id),
+    run_id TEXT REFERENCES job_runs(run_id) ON DELETE CASCADE,
+    task_order INT,
+    loader_index INT,
+    loader_type TEXT,
     warehouse_table TEXT NOT NULL,
-    file_path TEXT NOT NULL,
+    file_path TEXT,
     row_count BIGINT NOT NULL,
+    bytes_written BIGINT,
+    duration_ms BIGINT NOT NULL DEFAULT 0,
+    schema_hash TEXT,
     created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
     CONSTRAINT fk_job FOREIGN KEY (job_id) REFERENCES job_definitions(job_id) ON DELETE CASCADE
 );
 
+CREATE TABLE IF NOT EXISTS check_results (
+    id TEXT PRIMARY KEY,
+    run_id TEXT NOT NULL REFERENCES job_runs(run_id) ON DELETE CASCADE,
+    job_id TEXT NOT NULL REFERENCES job_definitions(job_id) ON DELETE CASCADE,
+    task_order INT NOT NULL,
+    check_order INT NOT NULL,
+    check_name TEXT NOT NULL,
+    column_name TEXT,
+    severity TEXT NOT NULL,
+    passed BOOLEAN NOT NULL,
+    failing_rows BIGINT,
+    message TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (run_id, task_order, check_order)
+);
+
+CREATE INDEX IF NOT EXISTS idx_check_results_run_id ON check_results(run_id);
+
+CREATE TABLE IF NOT EXISTS schema_snapshots (
+    id TEXT PRIMARY KEY,
+    run_id TEXT NOT NULL REFERENCES job_runs(run_id) ON DELETE CASCADE,
+    job_id TEXT NOT NULL REFERENCES job_definitions(job_id) ON DELETE CASCADE,
+    task_order INT NOT NULL,
+    columns TEXT NOT NULL,
+    schema_hash TEXT NOT NULL,
+    drift TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (run_id, task_order)
+);
+
+CREATE INDEX IF NOT EXISTS idx_schema_snapshots_job_task ON schema_snapshots(job_id, task_order);
+
+CREATE TABLE IF NOT EXISTS run_logs (
+    id TEXT PRIMARY KEY,
+    run_id TEXT NOT NULL REFERENCES job_runs(run_id) ON DELETE CASCADE,
+    task_order INT NOT NULL,
+    line_no INT NOT NULL,
+    stream TEXT NOT NULL,
+    message TEXT NOT NULL,
+    logged_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_logs_run_id ON run_logs(run_id, logged_at);
+
+CREATE TABLE IF NOT EXISTS secrets (
+    name TEXT PRIMARY KEY,
+    ciphertext TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS connections (
+    name TEXT PRIMARY KEY,
+    connection_type TEXT NOT NULL,
+    options TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
 CREATE INDEX IF NOT EXISTS idx_job_definitions_is_active ON job_definitions(is_active);
 CREATE INDEX IF NOT EXISTS idx_job_runs_status ON job_runs(status);
 CREATE INDEX IF NOT EXISTS idx_job_runs_job_id ON job_runs(job_id);
@@ -162,6 +404,8 @@ CREATE INDEX IF NOT EXISTS idx_task_definitions_job_id ON task_definitions(job_i
 CREATE INDEX IF NOT EXISTS idx_users_username ON users(username);
 CREATE INDEX IF NOT EXISTS idx_job_results_job_id ON job_results(job_id);
 CREATE INDEX IF NOT EXISTS idx_job_results_created_at ON job_results(created_at);
+CREATE INDEX IF NOT EXISTS idx_job_results_run_id ON job_results(run_id);
+CREATE UNIQUE INDEX IF NOT EXISTS ux_job_results_loader ON job_results(run_id, task_order, loader_index);
 "#;
                 for statement in schema.split(';') {
                     let trimmed = statement.trim();
@@ -182,31 +426,34 @@ CREATE INDEX IF NOT EXISTS idx_job_results_created_at ON job_results(created_at)
         description: Option<&str>,
         schedule: &str,
         is_active: bool,
+        params_schema: Option<&Value>,
     ) -> Result<JobDefinition> {
         let job_id = Uuid::new_v4();
         match &self.pool {
             DbPool::Pg(pool) => {
                 let job = sqlx::query_as::<_, JobDefinition>(
-                    "INSERT INTO job_definitions (job_id, job_name, description, schedule, is_active) VALUES ($1, $2, $3, $4, $5) RETURNING *"
+                    "INSERT INTO job_definitions (job_id, job_name, description, schedule, is_active, params_schema) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *"
                 )
                 .bind(job_id)
                 .bind(job_name)
                 .bind(description)
                 .bind(schedule)
                 .bind(is_active)
+                .bind(params_schema)
                 .fetch_one(pool)
                 .await?;
                 Ok(job)
             }
             DbPool::Sqlite(pool) => {
                 let job = sqlx::query_as::<_, JobDefinition>(
-                    "INSERT INTO job_definitions (job_id, job_name, description, schedule, is_active) VALUES (?, ?, ?, ?, ?) RETURNING *"
+                    "INSERT INTO job_definitions (job_id, job_name, description, schedule, is_active, params_schema) VALUES (?, ?, ?, ?, ?, ?) RETURNING *"
                 )
                 .bind(job_id)
                 .bind(job_name)
                 .bind(description)
                 .bind(schedule)
                 .bind(is_active)
+                .bind(params_schema)
                 .fetch_one(pool)
                 .await?;
                 Ok(job)
@@ -252,37 +499,43 @@ CREATE INDEX IF NOT EXISTS idx_job_results_created_at ON job_results(created_at)
 
     // --- Task Definitions ---
 
-    pub async fn create_task_definition(
-        &self,
-        job_id: Uuid,
-        task_order: i32,
-        extractor_config: &Value,
-        loader_config: &Value,
-    ) -> Result<TaskDefinition> {
+    pub async fn create_task_definition(&self, task: &NewTaskDefinition) -> Result<TaskDefinition> {
         let task_id = Uuid::new_v4();
         match &self.pool {
             DbPool::Pg(pool) => {
                 let task = sqlx::query_as::<_, TaskDefinition>(
-                    "INSERT INTO task_definitions (task_id, job_id, task_order, extractor_config, loader_config) VALUES ($1, $2, $3, $4, $5) RETURNING *"
+                    "INSERT INTO task_definitions (task_id, job_id, task_order, extractor_config, loader_config, transform_config, checks_config, fail_on_breaking_drift, masking_config, command_config, sql_config) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING *"
                 )
                 .bind(task_id)
-                .bind(job_id)
-                .bind(task_order)
-                .bind(extractor_config)
-                .bind(loader_config)
+                .bind(task.job_id)
+                .bind(task.task_order)
+                .bind(&task.extractor_config)
+                .bind(&task.loader_config)
+                .bind(&task.transform_config)
+                .bind(&task.checks_config)
+                .bind(task.fail_on_breaking_drift)
+                .bind(&task.masking_config)
+                .bind(&task.command_config)
+                .bind(&task.sql_config)
                 .fetch_one(pool)
                 .await?;
                 Ok(task)
             }
             DbPool::Sqlite(pool) => {
                 let task = sqlx::query_as::<_, TaskDefinition>(
-                    "INSERT INTO task_definitions (task_id, job_id, task_order, extractor_config, loader_config) VALUES (?, ?, ?, ?, ?) RETURNING *"
+                    "INSERT INTO task_definitions (task_id, job_id, task_order, extractor_config, loader_config, transform_config, checks_config, fail_on_breaking_drift, masking_config, command_config, sql_config) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING *"
                 )
                 .bind(task_id)
-                .bind(job_id)
-                .bind(task_order)
-                .bind(extractor_config)
-                .bind(loader_config)
+                .bind(task.job_id)
+                .bind(task.task_order)
+                .bind(&task.extractor_config)
+                .bind(&task.loader_config)
+                .bind(&task.transform_config)
+                .bind(&task.checks_config)
+                .bind(task.fail_on_breaking_drift)
+                .bind(&task.masking_config)
+                .bind(&task.command_config)
+                .bind(&task.sql_config)
                 .fetch_one(pool)
                 .await?;
                 Ok(task)
@@ -320,29 +573,32 @@ CREATE INDEX IF NOT EXISTS idx_job_results_created_at ON job_results(created_at)
         job_id: Uuid,
         status: &str,
         triggered_by: &str,
+        params: Option<&Value>,
     ) -> Result<JobRun> {
         let run_id = Uuid::new_v4();
         match &self.pool {
             DbPool::Pg(pool) => {
                 let run = sqlx::query_as::<_, JobRun>(
-                    "INSERT INTO job_runs (run_id, job_id, status, triggered_by) VALUES ($1, $2, $3, $4) RETURNING *"
+                    "INSERT INTO job_runs (run_id, job_id, status, triggered_by, params) VALUES ($1, $2, $3, $4, $5) RETURNING *"
                 )
                 .bind(run_id)
                 .bind(job_id)
                 .bind(status)
                 .bind(triggered_by)
+                .bind(params)
                 .fetch_one(pool)
                 .await?;
                 Ok(run)
             }
             DbPool::Sqlite(pool) => {
                 let run = sqlx::query_as::<_, JobRun>(
-                    "INSERT INTO job_runs (run_id, job_id, status, triggered_by) VALUES (?, ?, ?, ?) RETURNING *"
+                    "INSERT INTO job_runs (run_id, job_id, status, triggered_by, params) VALUES (?, ?, ?, ?, ?) RETURNING *"
                 )
                 .bind(run_id)
                 .bind(job_id)
                 .bind(status)
                 .bind(triggered_by)
+                .bind(params)
                 .fetch_one(pool)
                 .await?;
                 Ok(run)
@@ -416,6 +672,32 @@ CREATE INDEX IF NOT EXISTS idx_job_results_created_at ON job_results(created_at)
         }
     }
 
+    /// The job's latest successful run other than `run_id`.
+    pub async fn get_previous_successful_run(&self, job_id: Uuid, run_id: Uuid) -> Result<Option<JobRun>> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let run = sqlx::query_as::<_, JobRun>(
+                    "SELECT * FROM job_runs WHERE job_id = $1 AND run_id <> $2 AND status = 'success' ORDER BY created_at DESC LIMIT 1"
+                )
+                .bind(job_id)
+                .bind(run_id)
+                .fetch_optional(pool)
+                .await?;
+                Ok(run)
+            }
+            DbPool::Sqlite(pool) => {
+                let run = sqlx::query_as::<_, JobRun>(
+                    "SELECT * FROM job_runs WHERE job_id = ? AND run_id <> ? AND status = 'success' ORDER BY created_at DESC LIMIT 1"
+                )
+                .bind(job_id)
+                .bind(run_id)
+                .fetch_optional(pool)
+                .await?;
+                Ok(run)
+            }
+        }
+    }
+
     pub async fn update_job_run_status_with_error(
         &self,
         run_id: Uuid,
@@ -447,6 +729,26 @@ CREATE INDEX IF NOT EXISTS idx_job_results_created_at ON job_results(created_at)
         Ok(())
     }
 
+    pub async fn update_job_run_metrics(&self, run_id: Uuid, metrics: &Value) -> Result<()> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                sqlx::query("UPDATE job_runs SET metrics = $1, updated_at = NOW() WHERE run_id = $2")
+                    .bind(metrics)
+                    .bind(run_id)
+                    .execute(pool)
+                    .await?;
+            }
+            DbPool::Sqlite(pool) => {
+                sqlx::query("UPDATE job_runs SET metrics = ?, updated_at = CURRENT_TIMESTAMP WHERE run_id = ?")
+                    .bind(metrics)
+                    .bind(run_id)
+                    .execute(pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn get_all_job_runs(&self) -> Result<Vec<JobRun>> {
         match &self.pool {
             DbPool::Pg(pool) => {
@@ -483,6 +785,499 @@ CREATE INDEX IF NOT EXISTS idx_job_results_created_at ON job_results(created_at)
         }
     }
 
+    // --- Job Results ---
+
+    /// Records a loader's result, replacing the result of the same loader from an earlier
+    /// attempt of the run.
+    pub async fn record_job_result(&self, result: &NewJobResult) -> Result<JobResult> {
+        let id = Uuid::new_v4();
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let row = sqlx::query_as::<_, JobResult>(
+                    "INSERT INTO job_results (id, job_id, run_id, task_order, loader_index, loader_type, warehouse_table, file_path, row_count, bytes_written, duration_ms, schema_hash) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                     ON CONFLICT (run_id, task_order, loader_index) DO UPDATE SET loader_type = EXCLUDED.loader_type, warehouse_table = EXCLUDED.warehouse_table, file_path = EXCLUDED.file_path, row_count = EXCLUDED.row_count, bytes_written = EXCLUDED.bytes_written, duration_ms = EXCLUDED.duration_ms, schema_hash = EXCLUDED.schema_hash, created_at = NOW() RETURNING *"
+                )
+                .bind(id)
+                .bind(result.job_id)
+                .bind(result.run_id)
+                .bind(result.task_order)
+                .bind(result.loader_index)
+                .bind(&result.loader_type)
+                .bind(&result.warehouse_table)
+                .bind(&result.file_path)
+                .bind(result.row_count)
+                .bind(result.bytes_written)
+                .bind(result.duration_ms)
+                .bind(&result.schema_hash)
+                .fetch_one(pool)
+                .await?;
+                Ok(row)
+            }
+            DbPool::Sqlite(pool) => {
+                let row = sqlx::query_as::<_, JobResult>(
+                    "INSERT INTO job_results (id, job_id, run_id, task_order, loader_index, loader_type, warehouse_table, file_path, row_count, bytes_written, duration_ms, schema_hash) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                     ON CONFLICT (run_id, task_order, loader_index) DO UPDATE SET loader_type = excluded.loader_type, warehouse_table = excluded.warehouse_table, file_path = excluded.file_path, row_count = excluded.row_count, bytes_written = excluded.bytes_written, duration_ms = excluded.duration_ms, schema_hash = excluded.schema_hash, created_at = CURRENT_TIMESTAMP RETURNING *"
+                )
+                .bind(id)
+                .bind(result.job_id)
+                .bind(result.run_id)
+                .bind(result.task_order)
+                .bind(result.loader_index)
+                .bind(&result.loader_type)
+                .bind(&result.warehouse_table)
+                .bind(&result.file_path)
+                .bind(result.row_count)
+                .bind(result.bytes_written)
+                .bind(result.duration_ms)
+                .bind(&result.schema_hash)
+                .fetch_one(pool)
+                .await?;
+                Ok(row)
+            }
+        }
+    }
+
+    pub async fn get_job_results_for_job(&self, job_id: Uuid) -> Result<Vec<JobResult>> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let results = sqlx::query_as::<_, JobResult>(
+                    "SELECT * FROM job_results WHERE job_id = $1 ORDER BY created_at DESC"
+                )
+                .bind(job_id)
+                .fetch_all(pool)
+                .await?;
+                Ok(results)
+            }
+            DbPool::Sqlite(pool) => {
+                let results = sqlx::query_as::<_, JobResult>(
+                    "SELECT * FROM job_results WHERE job_id = ? ORDER BY created_at DESC"
+                )
+                .bind(job_id)
+                .fetch_all(pool)
+                .await?;
+                Ok(results)
+            }
+        }
+    }
+
+    pub async fn get_job_results_for_run(&self, run_id: Uuid) -> Result<Vec<JobResult>> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let results = sqlx::query_as::<_, JobResult>(
+                    "SELECT * FROM job_results WHERE run_id = $1 ORDER BY task_order ASC, created_at ASC"
+                )
+                .bind(run_id)
+                .fetch_all(pool)
+                .await?;
+                Ok(results)
+            }
+            DbPool::Sqlite(pool) => {
+                let results = sqlx::query_as::<_, JobResult>(
+                    "SELECT * FROM job_results WHERE run_id = ? ORDER BY task_order ASC, created_at ASC"
+                )
+                .bind(run_id)
+                .fetch_all(pool)
+                .await?;
+                Ok(results)
+            }
+        }
+    }
+
+    // --- Check Results ---
+
+    /// Records a check's outcome, replacing the outcome of the same check from an earlier
+    /// attempt of the run.
+    pub async fn record_check_result(&self, result: &NewCheckResult) -> Result<CheckResult> {
+        let id = Uuid::new_v4();
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let row = sqlx::query_as::<_, CheckResult>(
+                    "INSERT INTO check_results (id, run_id, job_id, task_order, check_order, check_name, column_name, severity, passed, failing_rows, message) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                     ON CONFLICT (run_id, task_order, check_order) DO UPDATE SET check_name = EXCLUDED.check_name, column_name = EXCLUDED.column_name, severity = EXCLUDED.severity, passed = EXCLUDED.passed, failing_rows = EXCLUDED.failing_rows, message = EXCLUDED.message, created_at = NOW() RETURNING *"
+                )
+                .bind(id)
+                .bind(result.run_id)
+                .bind(result.job_id)
+                .bind(result.task_order)
+                .bind(result.check_order)
+                .bind(&result.check_name)
+                .bind(&result.column_name)
+                .bind(&result.severity)
+                .bind(result.passed)
+                .bind(result.failing_rows)
+                .bind(&result.message)
+                .fetch_one(pool)
+                .await?;
+                Ok(row)
+            }
+            DbPool::Sqlite(pool) => {
+                let row = sqlx::query_as::<_, CheckResult>(
+                    "INSERT INTO check_results (id, run_id, job_id, task_order, check_order, check_name, column_name, severity, passed, failing_rows, message) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                     ON CONFLICT (run_id, task_order, check_order) DO UPDATE SET check_name = excluded.check_name, column_name = excluded.column_name, severity = excluded.severity, passed = excluded.passed, failing_rows = excluded.failing_rows, message = excluded.message, created_at = CURRENT_TIMESTAMP RETURNING *"
+                )
+                .bind(id)
+                .bind(result.run_id)
+                .bind(result.job_id)
+                .bind(result.task_order)
+                .bind(result.check_order)
+                .bind(&result.check_name)
+                .bind(&result.column_name)
+                .bind(&result.severity)
+                .bind(result.passed)
+                .bind(result.failing_rows)
+                .bind(&result.message)
+                .fetch_one(pool)
+                .await?;
+                Ok(row)
+            }
+        }
+    }
+
+    pub async fn get_check_results_for_run(&self, run_id: Uuid) -> Result<Vec<CheckResult>> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let results = sqlx::query_as::<_, CheckResult>(
+                    "SELECT * FROM check_results WHERE run_id = $1 ORDER BY task_order ASC, check_order ASC, created_at ASC"
+                )
+                .bind(run_id)
+                .fetch_all(pool)
+                .await?;
+                Ok(results)
+            }
+            DbPool::Sqlite(pool) => {
+                let results = sqlx::query_as::<_, CheckResult>(
+                    "SELECT * FROM check_results WHERE run_id = ? ORDER BY task_order ASC, check_order ASC, created_at ASC"
+                )
+                .bind(run_id)
+                .fetch_all(pool)
+                .await?;
+                Ok(results)
+            }
+        }
+    }
+
+    // --- Schema Snapshots ---
+
+    /// Stores the snapshot, replacing one left for the same task by an earlier attempt of the run.
+    pub async fn save_schema_snapshot(&self, snapshot: &NewSchemaSnapshot) -> Result<SchemaSnapshot> {
+        let id = Uuid::new_v4();
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let row = sqlx::query_as::<_, SchemaSnapshot>(
+                    "INSERT INTO schema_snapshots (id, run_id, job_id, task_order, columns, schema_hash, drift) VALUES ($1, $2, $3, $4, $5, $6, $7) \
+                     ON CONFLICT (run_id, task_order) DO UPDATE SET columns = EXCLUDED.columns, schema_hash = EXCLUDED.schema_hash, drift = EXCLUDED.drift, created_at = NOW() RETURNING *"
+                )
+                .bind(id)
+                .bind(snapshot.run_id)
+                .bind(snapshot.job_id)
+                .bind(snapshot.task_order)
+                .bind(&snapshot.columns)
+                .bind(&snapshot.schema_hash)
+                .bind(&snapshot.drift)
+                .fetch_one(pool)
+                .await?;
+                Ok(row)
+            }
+            DbPool::Sqlite(pool) => {
+                let row = sqlx::query_as::<_, SchemaSnapshot>(
+                    "INSERT INTO schema_snapshots (id, run_id, job_id, task_order, columns, schema_hash, drift) VALUES (?, ?, ?, ?, ?, ?, ?) \
+                     ON CONFLICT (run_id, task_order) DO UPDATE SET columns = excluded.columns, schema_hash = excluded.schema_hash, drift = excluded.drift, created_at = CURRENT_TIMESTAMP RETURNING *"
+                )
+                .bind(id)
+                .bind(snapshot.run_id)
+                .bind(snapshot.job_id)
+                .bind(snapshot.task_order)
+                .bind(&snapshot.columns)
+                .bind(&snapshot.schema_hash)
+                .bind(&snapshot.drift)
+                .fetch_one(pool)
+                .await?;
+                Ok(row)
+            }
+        }
+    }
+
+    /// Latest snapshot of the task from a successful run of the job other than `run_id`.
+    pub async fn get_previous_schema_snapshot(
+        &self,
+        job_id: Uuid,
+        task_order: i32,
+        run_id: Uuid,
+    ) -> Result<Option<SchemaSnapshot>> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let row = sqlx::query_as::<_, SchemaSnapshot>(
+                    "SELECT s.* FROM schema_snapshots s JOIN job_runs r ON r.run_id = s.run_id \
+                     WHERE s.job_id = $1 AND s.task_order = $2 AND s.run_id <> $3 AND r.status = 'success' \
+                     ORDER BY s.created_at DESC LIMIT 1"
+                )
+                .bind(job_id)
+                .bind(task_order)
+                .bind(run_id)
+                .fetch_optional(pool)
+                .await?;
+                Ok(row)
+            }
+            DbPool::Sqlite(pool) => {
+                let row = sqlx::query_as::<_, SchemaSnapshot>(
+                    "SELECT s.* FROM schema_snapshots s JOIN job_runs r ON r.run_id = s.run_id \
+                     WHERE s.job_id = ? AND s.task_order = ? AND s.run_id <> ? AND r.status = 'success' \
+                     ORDER BY s.created_at DESC LIMIT 1"
+                )
+                .bind(job_id)
+                .bind(task_order)
+                .bind(run_id)
+                .fetch_optional(pool)
+                .await?;
+                Ok(row)
+            }
+        }
+    }
+
+    pub async fn get_schema_snapshots_for_run(&self, run_id: Uuid) -> Result<Vec<SchemaSnapshot>> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let rows = sqlx::query_as::<_, SchemaSnapshot>(
+                    "SELECT * FROM schema_snapshots WHERE run_id = $1 ORDER BY task_order ASC"
+                )
+                .bind(run_id)
+                .fetch_all(pool)
+                .await?;
+                Ok(rows)
+            }
+            DbPool::Sqlite(pool) => {
+                let rows = sqlx::query_as::<_, SchemaSnapshot>(
+                    "SELECT * FROM schema_snapshots WHERE run_id = ? ORDER BY task_order ASC"
+                )
+                .bind(run_id)
+                .fetch_all(pool)
+                .await?;
+                Ok(rows)
+            }
+        }
+    }
+
+    // --- Run Logs ---
+
+    /// Appends the lines captured by one attempt of a run.
+    pub async fn append_run_logs(&self, run_id: Uuid, lines: &[LogLine]) -> Result<()> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let mut tx = pool.begin().await?;
+                for (line_no, line) in lines.iter().enumerate() {
+                    sqlx::query(
+                        "INSERT INTO run_logs (id, run_id, task_order, line_no, stream, message, logged_at) VALUES ($1, $2, $3, $4, $5, $6, $7)"
+                    )
+                    .bind(Uuid::new_v4())
+                    .bind(run_id)
+                    .bind(line.task_order)
+                    .bind(line_no as i32)
+                    .bind(&line.stream)
+                    .bind(&line.message)
+                    .bind(line.logged_at)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                tx.commit().await?;
+                Ok(())
+            }
+            DbPool::Sqlite(pool) => {
+                let mut tx = pool.begin().await?;
+                for (line_no, line) in lines.iter().enumerate() {
+                    sqlx::query(
+                        "INSERT INTO run_logs (id, run_id, task_order, line_no, stream, message, logged_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+                    )
+                    .bind(Uuid::new_v4())
+                    .bind(run_id)
+                    .bind(line.task_order)
+                    .bind(line_no as i32)
+                    .bind(&line.stream)
+                    .bind(&line.message)
+                    .bind(line.logged_at)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                tx.commit().await?;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn get_run_logs(&self, run_id: Uuid) -> Result<Vec<RunLogLine>> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let rows = sqlx::query_as::<_, RunLogLine>(
+                    "SELECT * FROM run_logs WHERE run_id = $1 ORDER BY logged_at ASC, line_no ASC"
+                )
+                .bind(run_id)
+                .fetch_all(pool)
+                .await?;
+                Ok(rows)
+            }
+            DbPool::Sqlite(pool) => {
+                let rows = sqlx::query_as::<_, RunLogLine>(
+                    "SELECT * FROM run_logs WHERE run_id = ? ORDER BY logged_at ASC, line_no ASC"
+                )
+                .bind(run_id)
+                .fetch_all(pool)
+                .await?;
+                Ok(rows)
+            }
+        }
+    }
+
+    // --- Secrets ---
+
+    /// Creates or replaces a secret.
+    pub async fn upsert_secret(&self, name: &str, ciphertext: &str) -> Result<StoredSecret> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let secret = sqlx::query_as::<_, StoredSecret>(
+                    "INSERT INTO secrets (name, ciphertext) VALUES ($1, $2)
+                     ON CONFLICT (name) DO UPDATE SET ciphertext = EXCLUDED.ciphertext, updated_at = NOW() RETURNING *"
+                )
+                .bind(name)
+                .bind(ciphertext)
+                .fetch_one(pool)
+                .await?;
+                Ok(secret)
+            }
+            DbPool::Sqlite(pool) => {
+                let secret = sqlx::query_as::<_, StoredSecret>(
+                    "INSERT INTO secrets (name, ciphertext) VALUES (?, ?)
+                     ON CONFLICT (name) DO UPDATE SET ciphertext = excluded.ciphertext, updated_at = CURRENT_TIMESTAMP RETURNING *"
+                )
+                .bind(name)
+                .bind(ciphertext)
+                .fetch_one(pool)
+                .await?;
+                Ok(secret)
+            }
+        }
+    }
+
+    pub async fn get_secret(&self, name: &str) -> Result<Option<StoredSecret>> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let secret = sqlx::query_as::<_, StoredSecret>("SELECT * FROM secrets WHERE name = $1")
+                    .bind(name)
+                    .fetch_optional(pool)
+                    .await?;
+                Ok(secret)
+            }
+            DbPool::Sqlite(pool) => {
+                let secret = sqlx::query_as::<_, StoredSecret>("SELECT * FROM secrets WHERE name = ?")
+                    .bind(name)
+                    .fetch_optional(pool)
+                    .await?;
+                Ok(secret)
+            }
+        }
+    }
+
+    pub async fn get_all_secrets(&self) -> Result<Vec<StoredSecret>> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let secrets = sqlx::query_as::<_, StoredSecret>("SELECT * FROM secrets ORDER BY name ASC")
+                    .fetch_all(pool)
+                    .await?;
+                Ok(secrets)
+            }
+            DbPool::Sqlite(pool) => {
+                let secrets = sqlx::query_as::<_, StoredSecret>("SELECT * FROM secrets ORDER BY name ASC")
+                    .fetch_all(pool)
+                    .await?;
+                Ok(secrets)
+            }
+        }
+    }
+
+    /// Deletes a secret, returning whether it existed.
+    pub async fn delete_secret(&self, name: &str) -> Result<bool> {
+        let deleted = match &self.pool {
+            DbPool::Pg(pool) => sqlx::query("DELETE FROM secrets WHERE name = $1").bind(name).execute(pool).await?.rows_affected(),
+            DbPool::Sqlite(pool) => sqlx::query("DELETE FROM secrets WHERE name = ?").bind(name).execute(pool).await?.rows_affected(),
+        };
+        Ok(deleted > 0)
+    }
+
+    // --- Connections ---
+
+    /// Creates or replaces a connection.
+    pub async fn upsert_connection(&self, name: &str, connection_type: &str, options: &Value) -> Result<ConnectionDefinition> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let connection = sqlx::query_as::<_, ConnectionDefinition>(
+                    "INSERT INTO connections (name, connection_type, options) VALUES ($1, $2, $3)
+                     ON CONFLICT (name) DO UPDATE SET connection_type = EXCLUDED.connection_type, options = EXCLUDED.options, updated_at = NOW() RETURNING *"
+                )
+                .bind(name)
+                .bind(connection_type)
+                .bind(options)
+                .fetch_one(pool)
+                .await?;
+                Ok(connection)
+            }
+            DbPool::Sqlite(pool) => {
+                let connection = sqlx::query_as::<_, ConnectionDefinition>(
+                    "INSERT INTO connections (name, connection_type, options) VALUES (?, ?, ?)
+                     ON CONFLICT (name) DO UPDATE SET connection_type = excluded.connection_type, options = excluded.options, updated_at = CURRENT_TIMESTAMP RETURNING *"
+                )
+                .bind(name)
+                .bind(connection_type)
+                .bind(options)
+                .fetch_one(pool)
+                .await?;
+                Ok(connection)
+            }
+        }
+    }
+
+    pub async fn get_connection(&self, name: &str) -> Result<Option<ConnectionDefinition>> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let connection = sqlx::query_as::<_, ConnectionDefinition>("SELECT * FROM connections WHERE name = $1")
+                    .bind(name)
+                    .fetch_optional(pool)
+                    .await?;
+                Ok(connection)
+            }
+            DbPool::Sqlite(pool) => {
+                let connection = sqlx::query_as::<_, ConnectionDefinition>("SELECT * FROM connections WHERE name = ?")
+                    .bind(name)
+                    .fetch_optional(pool)
+                    .await?;
+                Ok(connection)
+            }
+        }
+    }
+
+    pub async fn get_all_connections(&self) -> Result<Vec<ConnectionDefinition>> {
+        match &self.pool {
+            DbPool::Pg(pool) => {
+                let connections = sqlx::query_as::<_, ConnectionDefinition>("SELECT * FROM connections ORDER BY name ASC")
+                    .fetch_all(pool)
+                    .await?;
+                Ok(connections)
+            }
+            DbPool::Sqlite(pool) => {
+                let connections = sqlx::query_as::<_, ConnectionDefinition>("SELECT * FROM connections ORDER BY name ASC")
+                    .fetch_all(pool)
+                    .await?;
+                Ok(connections)
+            }
+        }
+    }
+
+    /// Deletes a connection, returning whether it existed.
+    pub async fn delete_connection(&self, name: &str) -> Result<bool> {
+        let deleted = match &self.pool {
+            DbPool::Pg(pool) => sqlx::query("DELETE FROM connections WHERE name = $1").bind(name).execute(pool).await?.rows_affected(),
+            DbPool::Sqlite(pool) => sqlx::query("DELETE FROM connections WHERE name = ?").bind(name).execute(pool).await?.rows_affected(),
+        };
+        Ok(deleted > 0)
+    }
+
     // --- User Management ---
 
     pub async fn create_user(&self, username: &str, password_hash: &str) -> Result<User> {
@@ -0,0 +1,242 @@
+//! Templated task configs, rendered with the context of the run.
+//!
+//! Any string in a task config may contain `{{ variable }}` expressions, which the worker
+//! replaces when the task runs, so paths and URLs can depend on the run, e.g.
+//! `data/{{ ds }}/users.csv` or `?since={{ prev_success_ts }}`. The variables are:
+//!
+//! * `ds`, `ds_nodash`: the run's logical date, as `2026-10-18` and `20261018`.
+//! * `ts`, `ts_nodash`: the run's logical timestamp (when it was queued), as RFC 3339 and
+//!   `20261018T063000`, the latter for file names.
+//! * `run_id`, `job_id`, `job_name`.
+//! * `prev_success_ts`, `prev_success_ds`: the logical timestamp and date of the job's previous
+//!   successful run, or null before the first one.
+//! * `params.NAME`: a parameter of the run, which the job must declare (see `orchestrator::params`).
+//!
+//! A string that is a single expression takes the variable's JSON value, so a parameter can fill
+//! a numeric field. Inside a longer string, values are formatted as text, with null as empty.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use std::ops::Range;
+use uuid::Uuid;
+
+use crate::orchestrator::params::ParamSchema;
+use crate::plugins::config::ConfigError;
+use crate::state::db::{Db, JobDefinition};
+
+const VARIABLES: &[&str] = &["ds", "ds_nodash", "ts", "ts_nodash", "run_id", "job_id", "job_name", "prev_success_ts", "prev_success_ds"];
+const PARAMS_PREFIX: &str = "params.";
+
+/// What a config is rendered with.
+#[derive(Debug, Clone)]
+pub struct RunContext {
+    pub run_id: Uuid,
+    pub job_id: Uuid,
+    pub job_name: String,
+    pub logical_ts: DateTime<Utc>,
+    pub prev_success_ts: Option<DateTime<Utc>>,
+    pub params: Map<String, Value>,
+}
+
+/// Parses a logical date given as `2026-10-18` (midnight UTC) or an RFC 3339 timestamp.
+pub fn parse_logical_date(text: &str) -> Result<DateTime<Utc>> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(text) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .with_context(|| format!("'{}' is neither a date (YYYY-MM-DD) nor an RFC 3339 timestamp", text))?;
+    Ok(date.and_hms_opt(0, 0, 0).expect("midnight is a valid time").and_utc())
+}
+
+/// Spans and trimmed contents of the `{{ ... }}` expressions in `text`.
+fn expressions(text: &str) -> Result<Vec<(Range<usize>, &str)>, String> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("{{").map(|i| offset + i) {
+        let Some(end) = text[start..].find("}}").map(|i| start + i + 2) else {
+            return Err(format!("unterminated template expression in '{}'", text));
+        };
+        let name = text[start + 2..end - 2].trim();
+        check_variable(name)?;
+        found.push((start..end, name));
+        offset = end;
+    }
+    Ok(found)
+}
+
+fn check_variable(name: &str) -> Result<(), String> {
+    match name.strip_prefix(PARAMS_PREFIX) {
+        Some(param) if !param.is_empty() && param.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => Ok(()),
+        Some(_) => Err(format!("invalid parameter name in '{{{{ {} }}}}'", name)),
+        None if VARIABLES.contains(&name) => Ok(()),
+        None => Err(format!("unknown template variable '{}'", name)),
+    }
+}
+
+/// Checks every template expression in a config names a known variable or a parameter the job
+/// declares.
+pub fn check_templates(config: &Value, params: &ParamSchema) -> Result<(), ConfigError> {
+    match config {
+        Value::String(text) => {
+            let undeclared = |(_, name): &(Range<usize>, &str)| {
+                let param = name.strip_prefix(PARAMS_PREFIX)?;
+                (!params.declares(param)).then(|| format!("parameter '{}' is not declared in the job's params", param))
+            };
+            let found = expressions(text).map_err(|e| ConfigError::new("", e))?;
+            match found.iter().find_map(undeclared) {
+                Some(message) => Err(ConfigError::new("", message)),
+                None => Ok(()),
+            }
+        }
+        Value::Array(items) => {
+            let mut errors = ConfigError::default();
+            for (i, item) in items.iter().enumerate() {
+                errors.merge(&format!("[{}]", i), check_templates(item, params));
+            }
+            errors.into_result()
+        }
+        Value::Object(fields) => {
+            let mut errors = ConfigError::default();
+            for (key, value) in fields {
+                errors.merge(key, check_templates(value, params));
+            }
+            errors.into_result()
+        }
+        _ => Ok(()),
+    }
+}
+
+fn format_ts(ts: DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+impl RunContext {
+    /// The context of a run of `job` at `logical_ts`, with the job's previous successful run
+    /// other than `run_id`.
+    pub async fn for_run(db: &Db, job: &JobDefinition, run_id: Uuid, logical_ts: DateTime<Utc>, params: Map<String, Value>) -> Result<Self> {
+        let previous = db.get_previous_successful_run(job.job_id, run_id).await?;
+        Ok(Self {
+            run_id,
+            job_id: job.job_id,
+            job_name: job.job_name.clone(),
+            logical_ts,
+            prev_success_ts: previous.map(|run| run.created_at),
+            params,
+        })
+    }
+
+    /// All variables, with the parameters nested under `params`.
+    pub fn variables(&self) -> Value {
+        json!({
+            "ds": self.logical_ts.format("%Y-%m-%d").to_string(),
+            "ds_nodash": self.logical_ts.format("%Y%m%d").to_string(),
+            "ts": format_ts(self.logical_ts),
+            "ts_nodash": self.logical_ts.format("%Y%m%dT%H%M%S").to_string(),
+            "run_id": self.run_id,
+            "job_id": self.job_id,
+            "job_name": self.job_name,
+            "prev_success_ts": self.prev_success_ts.map(format_ts),
+            "prev_success_ds": self.prev_success_ts.map(|ts| ts.format("%Y-%m-%d").to_string()),
+            "params": self.params,
+        })
+    }
+
+    fn lookup(&self, variables: &Value, name: &str) -> Result<Value> {
+        match name.strip_prefix(PARAMS_PREFIX) {
+            Some(param) => self.params.get(param).cloned().with_context(|| format!("Run parameter '{}' is not set", param)),
+            None => Ok(variables[name].clone()),
+        }
+    }
+
+    /// Renders every template expression in a config.
+    pub fn render(&self, config: &Value) -> Result<Value> {
+        self.render_with(&self.variables(), config)
+    }
+
+    fn render_with(&self, variables: &Value, config: &Value) -> Result<Value> {
+        Ok(match config {
+            Value::String(text) => {
+                let found = expressions(text).map_err(anyhow::Error::msg)?;
+                if let [(span, name)] = found.as_slice() {
+                    if *span == (0..text.len()) {
+                        return self.lookup(variables, name);
+                    }
+                }
+                let mut rendered = String::with_capacity(text.len());
+                let mut last = 0;
+                for (span, name) in found {
+                    rendered.push_str(&text[last..span.start]);
+                    match self.lookup(variables, name)? {
+                        Value::String(value) => rendered.push_str(&value),
+                        Value::Null => {}
+                        value => rendered.push_str(&value.to_string()),
+                    }
+                    last = span.end;
+                }
+                rendered.push_str(&text[last..]);
+                Value::String(rendered)
+            }
+            Value::Array(items) => Value::Array(items.iter().map(|item| self.render_with(variables, item)).collect::<Result<_>>()?),
+            Value::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), self.render_with(variables, value)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> RunContext {
+        RunContext {
+            run_id: Uuid::nil(),
+            job_id: Uuid::nil(),
+            job_name: "users".to_string(),
+            logical_ts: parse_logical_date("2026-10-18T06:30:00Z").unwrap(),
+            prev_success_ts: None,
+            params: Map::from_iter([("limit".to_string(), json!(500))]),
+        }
+    }
+
+    #[test]
+    fn test_renders_run_context_into_configs() {
+        let mut ctx = context();
+        let config = json!({
+            "path": "data/{{ ds }}/{{job_name}}.csv",
+            "url": "https://api.example.com/posts?since={{ prev_success_ts }}&n={{ params.limit }}",
+            "limit": "{{ params.limit }}",
+            "columns": ["{{ ds_nodash }}", "{{ ts_nodash }}"]
+        });
+        assert_eq!(
+            ctx.render(&config).unwrap(),
+            json!({
+                "path": "data/2026-10-18/users.csv",
+                "url": "https://api.example.com/posts?since=&n=500",
+                "limit": 500,
+                "columns": ["20261018", "20261018T063000"]
+            })
+        );
+
+        ctx.prev_success_ts = Some(parse_logical_date("2026-10-17").unwrap());
+        assert_eq!(ctx.render(&json!("{{ prev_success_ts }}")).unwrap(), json!("2026-10-17T00:00:00Z"));
+        let err = ctx.render(&json!("{{ params.since }}")).unwrap_err();
+        assert_eq!(err.to_string(), "Run parameter 'since' is not set");
+    }
+
+    #[test]
+    fn test_rejects_unknown_variables() {
+        let params = ParamSchema::parse(&json!({ "limit": { "type": "integer" } })).unwrap();
+        let config = json!({ "a": "{{ dt }}", "b": ["{{ params. }}"], "c": "{{ ds", "d": "{ ds }", "e": "{{ params.limit }}{{ params.since }}" });
+        let errors = check_templates(&config, &params).unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["a", "b[0]", "c", "e"]);
+        assert_eq!(errors.errors[0].message, "unknown template variable 'dt'");
+        assert_eq!(errors.errors[3].message, "parameter 'since' is not declared in the job's params");
+    }
+}
@@ -10,6 +10,7 @@
  */
 
 use anyhow::{Context, Result};
+use crate::connections;
 use crate::state::db::Db;
 use crate::orchestrator::job_manager::{JobManager, NewTask};
 use serde::Deserialize;
@@ -23,13 +24,23 @@ pub struct SeedJob {
     pub description: Option<String>,
     pub schedule: String,
     pub is_active: bool,
+    pub params: Option<Value>,
     pub tasks: Vec<SeedTask>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SeedTask {
+    #[serde(default)]
     pub extractor_config: Value,
+    #[serde(default)]
     pub loader_config: Value,
+    pub transform: Option<Value>,
+    pub checks: Option<Value>,
+    #[serde(default)]
+    pub fail_on_breaking_drift: bool,
+    pub masking: Option<Value>,
+    pub command: Option<Value>,
+    pub sql: Option<Value>,
 }
 
 pub async fn seed_jobs(db: &Db, file_path: &str) -> Result<()> {
@@ -38,8 +49,28 @@ pub async fn seed_jobs(db: &Db, file_path: &str) -> Result<()> {
     let jobs: Vec<SeedJob> = serde_yaml::from_str(&contents).context("Failed to parse YAML")?;
 
     let job_manager = JobManager::new(db.clone());
+    let connections = connections::load(db).await?;
 
-    for job_data in jobs {
+    // Validate the whole file first, so a broken job does not leave the others half-seeded.
+    let jobs: Vec<(SeedJob, Vec<NewTask>)> = jobs
+        .into_iter()
+        .map(|mut job_data| {
+            let tasks: Vec<NewTask> = std::mem::take(&mut job_data.tasks).into_iter().map(|t| NewTask {
+                extractor_config: t.extractor_config,
+                loader_config: t.loader_config,
+                transform_config: t.transform,
+                checks_config: t.checks,
+                fail_on_breaking_drift: t.fail_on_breaking_drift,
+                masking_config: t.masking,
+                command_config: t.command,
+                sql_config: t.sql,
+            }).collect();
+            JobManager::validate_job(job_data.params.as_ref(), &tasks, &connections).context(format!("Job '{}' in {} is invalid", job_data.job_id, file_path))?;
+            Ok((job_data, tasks))
+        })
+        .collect::<Result<_>>()?;
+
+    for (job_data, tasks) in jobs {
         // Check if job already exists (by name, which corresponds to YAML's job_id)
         // Since get_job takes a UUID, we can't easily check by name with current API.
         // But let's check all jobs and filter. This is slow but fine for seeding.
@@ -49,17 +80,13 @@ pub async fn seed_jobs(db: &Db, file_path: &str) -> Result<()> {
             continue;
         }
 
-        let tasks: Vec<NewTask> = job_data.tasks.into_iter().map(|t| NewTask {
-            extractor_config: t.extractor_config,
-            loader_config: t.loader_config,
-        }).collect();
-
         info!("Creating job: {}", job_data.job_id);
         job_manager.create_job(
             &job_data.job_id,
             job_data.description.as_deref(),
             &job_data.schedule,
             job_data.is_active,
+            job_data.params,
             tasks,
         ).await?;
     }
@@ -0,0 +1,120 @@
+//! Schema drift detection between runs.
+//!
+//! The worker snapshots the schema of every task's extracted frame and compares it with the
+//! snapshot from the job's previous successful run. Added columns are reported as drift;
+//! removed and retyped columns are breaking drift, which can optionally fail the task.
+
+use polars::prelude::Schema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnType {
+    pub name: String,
+    pub dtype: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetypedColumn {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaDrift {
+    pub added: Vec<ColumnType>,
+    pub removed: Vec<ColumnType>,
+    pub retyped: Vec<RetypedColumn>,
+}
+
+impl SchemaDrift {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.retyped.is_empty()
+    }
+
+    /// Removed or retyped columns can break downstream consumers.
+    pub fn is_breaking(&self) -> bool {
+        !self.removed.is_empty() || !self.retyped.is_empty()
+    }
+
+    /// One-line description, e.g. `added [a]; removed [b]; retyped [c: i64 -> str]`.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.added.is_empty() {
+            parts.push(format!("added [{}]", self.added.iter().map(|c| c.name.as_str()).collect::<Vec<_>>().join(", ")));
+        }
+        if !self.removed.is_empty() {
+            parts.push(format!("removed [{}]", self.removed.iter().map(|c| c.name.as_str()).collect::<Vec<_>>().join(", ")));
+        }
+        if !self.retyped.is_empty() {
+            let retyped: Vec<String> = self.retyped.iter().map(|c| format!("{}: {} -> {}", c.name, c.from, c.to)).collect();
+            parts.push(format!("retyped [{}]", retyped.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Column names and types of a frame's schema, in column order.
+pub fn schema_columns(schema: &Schema) -> Vec<ColumnType> {
+    schema
+        .iter()
+        .map(|(name, dtype)| ColumnType {
+            name: name.to_string(),
+            dtype: dtype.to_string(),
+        })
+        .collect()
+}
+
+/// Compares the current columns with the previous ones. Column order is ignored.
+pub fn diff_schemas(previous: &[ColumnType], current: &[ColumnType]) -> SchemaDrift {
+    let mut drift = SchemaDrift::default();
+    for column in current {
+        match previous.iter().find(|p| p.name == column.name) {
+            None => drift.added.push(column.clone()),
+            Some(p) if p.dtype != column.dtype => drift.retyped.push(RetypedColumn {
+                name: column.name.clone(),
+                from: p.dtype.clone(),
+                to: column.dtype.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    drift.removed = previous
+        .iter()
+        .filter(|p| !current.iter().any(|c| c.name == p.name))
+        .cloned()
+        .collect();
+    drift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::prelude::*;
+
+    #[test]
+    fn test_diff_reports_added_removed_and_retyped_columns() {
+        let before = df!("id" => &[1i64], "name" => &["a"], "score" => &[1i64]).unwrap();
+        let after = df!("score" => &[1.5f64], "id" => &[1i64], "email" => &["a@x.io"]).unwrap();
+
+        let drift = diff_schemas(&schema_columns(&before.schema()), &schema_columns(&after.schema()));
+
+        assert_eq!(drift.added.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["email"]);
+        assert_eq!(drift.removed.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["name"]);
+        assert_eq!(drift.retyped.len(), 1);
+        assert!(drift.is_breaking());
+        assert_eq!(drift.summary(), "added [email]; removed [name]; retyped [score: i64 -> f64]");
+    }
+
+    #[test]
+    fn test_added_columns_are_not_breaking() {
+        let before = df!("id" => &[1i64]).unwrap();
+        let after = df!("id" => &[1i64], "extra" => &[true]).unwrap();
+
+        let drift = diff_schemas(&schema_columns(&before.schema()), &schema_columns(&after.schema()));
+
+        assert!(!drift.is_empty());
+        assert!(!drift.is_breaking());
+        assert!(diff_schemas(&schema_columns(&before.schema()), &schema_columns(&before.schema())).is_empty());
+    }
+}
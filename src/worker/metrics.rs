@@ -0,0 +1,114 @@
+//! Run-level metrics recorded by the worker.
+//!
+//! After every attempt the worker stores a `RunMetrics` summary in `job_runs.metrics`, so
+//! the run record shows how each task and loader fared, including partial deliveries.
+
+use serde::Serialize;
+
+use crate::plugins::command::CommandOutcome;
+use crate::plugins::sql_task::SqlOutcome;
+use crate::plugins::LoadReport;
+use crate::worker::drift::SchemaDrift;
+use crate::worker::profile::ColumnProfile;
+
+#[derive(Debug, Default, Serialize, Clone)]
+pub struct RunMetrics {
+    /// The attempt that recorded these metrics, counting from 1.
+    pub attempt: u32,
+    pub tasks: Vec<TaskMetrics>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TaskMetrics {
+    pub task_order: i32,
+    pub rows_extracted: usize,
+    /// Rows left after the task's transform steps, when it has any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows_transformed: Option<usize>,
+    /// Rows removed by `dedupe` transform steps.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows_deduplicated: Option<usize>,
+    /// Columns added, removed or retyped since the previous successful run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_drift: Option<SchemaDrift>,
+    /// Per-column statistics of the extracted data.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub profile: Vec<ColumnProfile>,
+    /// Audit record of the masking policies applied before loading.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub masking: Vec<serde_json::Value>,
+    /// Exit code and duration of a command task, which extracts and loads nothing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<CommandOutcome>,
+    /// Rows affected by each statement of a SQL task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sql: Option<SqlOutcome>,
+    pub loaders: Vec<LoaderMetrics>,
+}
+
+impl TaskMetrics {
+    pub fn new(task_order: i32, rows_extracted: usize) -> Self {
+        Self {
+            task_order,
+            rows_extracted,
+            rows_transformed: None,
+            rows_deduplicated: None,
+            schema_drift: None,
+            profile: Vec::new(),
+            masking: Vec::new(),
+            command: None,
+            sql: None,
+            loaders: Vec::new(),
+        }
+    }
+}
+
+/// Outcome of a single loader: `success`, `partial` (some data delivered) or `failed`.
+#[derive(Debug, Serialize, Clone)]
+pub struct LoaderMetrics {
+    pub index: usize,
+    pub loader_type: String,
+    pub required: bool,
+    pub status: &'static str,
+    pub rows: i64,
+    pub duration_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl LoaderMetrics {
+    pub fn from_report(
+        index: usize,
+        loader_type: &str,
+        required: bool,
+        duration_ms: i64,
+        report: &LoadReport,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            index,
+            loader_type: loader_type.to_string(),
+            required,
+            status: if error.is_some() { "partial" } else { "success" },
+            rows: report.row_count,
+            duration_ms,
+            details: report.details.clone(),
+            error,
+        }
+    }
+
+    pub fn failed(index: usize, loader_type: &str, required: bool, duration_ms: i64, error: String) -> Self {
+        Self {
+            index,
+            loader_type: loader_type.to_string(),
+            required,
+            status: "failed",
+            rows: 0,
+            duration_ms,
+            details: None,
+            error: Some(error),
+        }
+    }
+}
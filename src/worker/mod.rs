@@ -1,18 +1,46 @@
 // In src/worker/mod.rs
 
-use crate::plugins::extractors::api_extractor::ApiExtractor;
-use crate::plugins::extractors::csv_extractor::CsvExtractor;
-use crate::plugins::extractors::parquet_extractor::ParquetExtractor;
-use crate::plugins::loaders::duckdb_loader::DuckDBLoader;
-use crate::plugins::{Extractor, Loader};
-use crate::state::db::{Db, JobRun};
+use crate::plugins::checks::{parse_checks, run_checks, Severity};
+use crate::plugins::command::parse_command;
+use crate::plugins::sql_task::parse_sql_task;
+use crate::plugins::masking::{apply_masking, parse_masking};
+use crate::plugins::registry::registry;
+use crate::plugins::transforms::{apply_transforms, parse_transforms, source_columns};
+use crate::plugins::{schema_hash, LoadReport, Loader, PartialLoadError, RunLog, TaskContext};
+use crate::connections::{self, Connections};
+use crate::secrets::SecretStore;
+use crate::templates::RunContext;
+use crate::state::db::{Db, JobRun, NewCheckResult, NewJobResult, NewSchemaSnapshot, TaskDefinition};
+use drift::{diff_schemas, schema_columns, ColumnType};
+use metrics::{LoaderMetrics, RunMetrics, TaskMetrics};
+use profile::profile_frame;
 use anyhow::{Context, Result};
-use serde_json::Value;
+use polars::prelude::DataFrame;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
 use tokio::time::sleep;
 
-use tracing::{info, error, debug}; // Added tracing imports
+use tracing::{info, error, debug, warn}; // Added tracing imports
+
+pub mod drift;
+pub mod metrics;
+pub mod profile;
+
+/// A failure that running the job again wouldn't fix, such as data failing its quality checks.
+/// The run fails on the first attempt instead of being retried.
+#[derive(Debug)]
+pub struct NonRetryable(pub String);
+
+impl std::fmt::Display for NonRetryable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for NonRetryable {}
 
 pub async fn run_worker(db: Db, job_run: JobRun) -> Result<()> {
     info!("Worker: Starting worker for job run: {}", job_run.run_id);
@@ -21,12 +49,12 @@ pub async fn run_worker(db: Db, job_run: JobRun) -> Result<()> {
     match result {
         Ok(_) => {
             info!("Worker: Job run {} completed successfully. Updating status to 'success'.", job_run.run_id);
-            db.update_job_run_status(job_run.run_id.clone(), "success").await.context(format!("Worker: Failed to update job run {} status to 'success'", job_run.run_id))?;
+            db.update_job_run_status(job_run.run_id, "success").await.context(format!("Worker: Failed to update job run {} status to 'success'", job_run.run_id))?;
             info!("Worker: Job run {} status updated to 'success'.", job_run.run_id);
         }
         Err(e) => {
             error!("Worker: Job run {} failed: {:?}. Updating status to 'failed'.", job_run.run_id, e);
-            db.update_job_run_status_with_error(job_run.run_id.clone(), "failed", &e.to_string()).await.context(format!("Worker: Failed to update job run {} status to 'failed'", job_run.run_id))?;
+            db.update_job_run_status_with_error(job_run.run_id, "failed", &format!("{:#}", e)).await.context(format!("Worker: Failed to update job run {} status to 'failed'", job_run.run_id))?;
             error!("Worker: Job run {} status updated to 'failed'.", job_run.run_id);
         }
     }
@@ -37,10 +65,12 @@ pub async fn run_worker(db: Db, job_run: JobRun) -> Result<()> {
 async fn execute_job_with_retries(db: &Db, job_run: &JobRun) -> Result<()> {
     let max_retries = 3;
     let mut attempts = 0;
+    let mut delivered = Delivered::default();
+    let mut finished = FinishedTasks::default();
     info!("Worker: Executing job run {} with max retries: {}", job_run.run_id, max_retries);
 
     loop {
-        match execute_job(db, job_run).await {
+        match execute_job(db, job_run, attempts + 1, &mut delivered, &mut finished).await {
             Ok(_) => {
                 info!("Worker: Job run {} completed successfully after {} attempts.", job_run.run_id, attempts + 1);
                 return Ok(());
@@ -48,6 +78,10 @@ async fn execute_job_with_retries(db: &Db, job_run: &JobRun) -> Result<()> {
             Err(e) => {
                 attempts += 1;
                 error!("Worker: Job run {} failed on attempt {}/{}: {:?}", job_run.run_id, attempts, max_retries, e);
+                if e.downcast_ref::<NonRetryable>().is_some() {
+                    error!("Worker: Job run {} failed with an error a retry wouldn't fix. No more retries.", job_run.run_id);
+                    return Err(e);
+                }
                 if attempts >= max_retries {
                     error!("Worker: Job run {} failed after {} attempts. No more retries.", job_run.run_id, max_retries);
                     return Err(e);
@@ -59,21 +93,307 @@ async fn execute_job_with_retries(db: &Db, job_run: &JobRun) -> Result<()> {
     }
 }
 
-async fn execute_job(db: &Db, job_run: &JobRun) -> Result<()> {
+/// Loaders that delivered their data on an earlier attempt of a run, keyed by task order and
+/// loader index, with the metrics they reported. A retry doesn't run them again.
+type Delivered = HashMap<(i32, usize), LoaderMetrics>;
+
+/// Command and SQL tasks that finished on an earlier attempt of a run, keyed by task order, with their
+/// metrics. A retry doesn't run them again.
+type FinishedTasks = HashMap<i32, TaskMetrics>;
+
+async fn execute_job(db: &Db, job_run: &JobRun, attempt: u32, delivered: &mut Delivered, finished: &mut FinishedTasks) -> Result<()> {
+    let mut metrics = RunMetrics { attempt, ..RunMetrics::default() };
+    let run_log = RunLog::default();
+    let result = execute_tasks(db, job_run, &mut metrics, &run_log, delivered, finished).await.map_err(|e| {
+        // Secret values must not reach the run's error message.
+        let message = format!("{:#}", e);
+        let redacted = run_log.redact(&message);
+        if redacted == message {
+            e
+        } else if e.downcast_ref::<NonRetryable>().is_some() {
+            NonRetryable(redacted).into()
+        } else {
+            anyhow::anyhow!(redacted)
+        }
+    });
+
+    // Metrics and logs are written for failed attempts too, so partial loads and the output
+    // explaining a failure stay visible on the run.
+    let lines = run_log.take();
+    if !lines.is_empty() {
+        if let Err(e) = db.append_run_logs(job_run.run_id, &lines).await {
+            warn!("Worker: Failed to record logs for run {}: {:?}", job_run.run_id, e);
+        }
+    }
+    match serde_json::to_value(&metrics) {
+        Ok(value) => {
+            if let Err(e) = db.update_job_run_metrics(job_run.run_id, &redact_value(&run_log, value)).await {
+                warn!("Worker: Failed to record metrics for run {}: {:?}", job_run.run_id, e);
+            }
+        }
+        Err(e) => warn!("Worker: Failed to serialize metrics for run {}: {:?}", job_run.run_id, e),
+    }
+    result
+}
+
+async fn execute_tasks(
+    db: &Db,
+    job_run: &JobRun,
+    metrics: &mut RunMetrics,
+    run_log: &RunLog,
+    delivered: &mut Delivered,
+    finished: &mut FinishedTasks,
+) -> Result<()> {
     info!("Worker: Executing job {} for run {}.", job_run.job_id, job_run.run_id);
-    let tasks = db.get_task_definitions_for_job(job_run.job_id.clone()).await.context(format!("Worker: Failed to get task definitions for job {}", job_run.job_id))?;
+    let tasks = db.get_task_definitions_for_job(job_run.job_id).await.context(format!("Worker: Failed to get task definitions for job {}", job_run.job_id))?;
+
+    let job = db.get_job_definition(job_run.job_id).await?.context(format!("Worker: Job {} no longer exists", job_run.job_id))?;
+    // Runs queued before parameters existed have none stored.
+    let params = match &job_run.params {
+        Some(Value::Object(params)) => params.clone(),
+        _ => Map::new(),
+    };
+    let run_context = RunContext::for_run(db, &job, job_run.run_id, job_run.created_at, params)
+        .await
+        .context(format!("Worker: Failed to build the run context for job {}", job_run.job_id))?;
+    let secrets = SecretStore::from_env(db.clone()).context("Worker: Failed to configure the secret store")?;
+    let connections = connections::load(db).await.context("Worker: Failed to load connections")?;
 
-    for (i, task) in tasks.into_iter().enumerate() {
+    for (i, mut task) in tasks.into_iter().enumerate() {
         info!("Worker: Processing task {} for job {}.", i + 1, job_run.job_id);
-        let extractor = get_extractor(&task.extractor_config).context(format!("Worker: Failed to get extractor for task {} in job {}", i + 1, job_run.job_id))?;
-        let loader = get_loader(&task.loader_config).context(format!("Worker: Failed to get loader for task {} in job {}", i + 1, job_run.job_id))?;
+        if let Some(task_metrics) = finished.get(&task.task_order) {
+            info!("Worker: Skipping task {} in job {}, which finished on an earlier attempt.", i + 1, job_run.job_id);
+            metrics.tasks.push(task_metrics.clone());
+            continue;
+        }
+        resolve_configs(&secrets, &connections, &run_context, &mut task, run_log).await.context(format!("Worker: Failed to resolve the configs of task {} in job {}", i + 1, job_run.job_id))?;
+        let ctx = TaskContext {
+            job_id: job_run.job_id,
+            run_id: job_run.run_id,
+            task_order: task.task_order,
+            run_log: run_log.clone(),
+        };
+        if let Some(config) = &task.command_config {
+            let command = parse_command(config).context(format!("Worker: Invalid command for task {} in job {}", i + 1, job_run.job_id))?;
+            info!("Worker: Running command '{}' for task {} in job {}.", command.command, i + 1, job_run.job_id);
+            metrics.tasks.push(TaskMetrics::new(task.task_order, 0));
+            let outcome = command.run(&ctx).await.context(format!("Worker: Command failed for task {} in job {}", i + 1, job_run.job_id))?;
+            info!("Worker: Command '{}' for task {} in job {} exited with code {}.", command.command, i + 1, job_run.job_id, outcome.exit_code);
+            let task_metrics = metrics.tasks.last_mut().expect("task metrics were just pushed");
+            task_metrics.command = Some(outcome);
+            finished.insert(task.task_order, task_metrics.clone());
+            continue;
+        }
+        if let Some(config) = &task.sql_config {
+            let sql = parse_sql_task(config).context(format!("Worker: Invalid SQL task {} in job {}", i + 1, job_run.job_id))?;
+            info!("Worker: Running SQL against '{}' for task {} in job {}.", sql.db_path, i + 1, job_run.job_id);
+            metrics.tasks.push(TaskMetrics::new(task.task_order, 0));
+            let task_ctx = ctx.clone();
+            let outcome = tokio::task::spawn_blocking(move || sql.run(&task_ctx))
+                .await
+                .context("SQL task panicked")?
+                .context(format!("Worker: SQL failed for task {} in job {}", i + 1, job_run.job_id))?;
+            info!("Worker: Ran {} SQL statement(s) for task {} in job {}.", outcome.statements.len(), i + 1, job_run.job_id);
+            let task_metrics = metrics.tasks.last_mut().expect("task metrics were just pushed");
+            task_metrics.sql = Some(outcome);
+            finished.insert(task.task_order, task_metrics.clone());
+            continue;
+        }
+        let extractor = registry().extractor(&task.extractor_config, &ctx).context(format!("Worker: Failed to get extractor for task {} in job {}", i + 1, job_run.job_id))?;
+        let loaders = get_loaders(&task.loader_config, &ctx).context(format!("Worker: Failed to get loaders for task {} in job {}", i + 1, job_run.job_id))?;
+        let transforms = match &task.transform_config {
+            Some(config) => parse_transforms(config).context(format!("Worker: Invalid transform for task {} in job {}", i + 1, job_run.job_id))?,
+            None => Vec::new(),
+        };
+        let checks = match &task.checks_config {
+            Some(config) => parse_checks(config).context(format!("Worker: Invalid checks for task {} in job {}", i + 1, job_run.job_id))?,
+            None => Vec::new(),
+        };
+        let masking = match &task.masking_config {
+            Some(config) => parse_masking(config).context(format!("Worker: Invalid masking for task {} in job {}", i + 1, job_run.job_id))?,
+            None => Vec::new(),
+        };
 
         info!("Worker: Extracting data for task {} in job {}.", i + 1, job_run.job_id);
         let df = extractor.extract().await.context(format!("Worker: Extraction failed for task {} in job {}", i + 1, job_run.job_id))?;
         info!("Worker: Data extracted for task {} in job {}. Rows: {}", i + 1, job_run.job_id, df.height()); // Assuming df has a height() method
+        metrics.tasks.push(TaskMetrics::new(task.task_order, df.height()));
+        let task_metrics = metrics.tasks.last_mut().expect("task metrics were just pushed");
+
+        // Masked columns are profiled without their values, which are not masked yet. Policies
+        // name columns as they are after transforms, so renames are followed back.
+        let masked_columns: Vec<String> = masking.iter().map(|p| p.column.clone()).collect();
+        let masked_columns = source_columns(&transforms, &masked_columns);
+        let masked_columns: Vec<&str> = masked_columns.iter().map(String::as_str).collect();
+        match profile_frame(&df, &masked_columns) {
+            Ok(profile) => task_metrics.profile = profile,
+            Err(e) => warn!("Worker: Failed to profile data for task {} in job {}: {:#}", i + 1, job_run.job_id, e),
+        }
+
+        let columns = schema_columns(&df.schema());
+        let previous = db.get_previous_schema_snapshot(job_run.job_id, task.task_order, job_run.run_id).await.context(format!("Worker: Failed to load previous schema for task {} in job {}", i + 1, job_run.job_id))?;
+        let drift = match previous {
+            Some(previous) => {
+                let previous_columns: Vec<ColumnType> = serde_json::from_value(previous.columns).context("Worker: Stored schema snapshot is malformed")?;
+                Some(diff_schemas(&previous_columns, &columns)).filter(|d| !d.is_empty())
+            }
+            None => None,
+        };
+        let snapshot = NewSchemaSnapshot {
+            run_id: job_run.run_id,
+            job_id: job_run.job_id,
+            task_order: task.task_order,
+            columns: serde_json::to_value(&columns)?,
+            schema_hash: schema_hash(&df.schema()),
+            drift: drift.as_ref().map(serde_json::to_value).transpose()?,
+        };
+        db.save_schema_snapshot(&snapshot).await.context(format!("Worker: Failed to record schema for task {} in job {}", i + 1, job_run.job_id))?;
+        if let Some(drift) = drift {
+            warn!("Worker: Schema drift for task {} in job {}: {}", i + 1, job_run.job_id, drift.summary());
+            let breaking = drift.is_breaking();
+            task_metrics.schema_drift = Some(drift.clone());
+            if breaking && task.fail_on_breaking_drift {
+                return Err(anyhow::Error::new(NonRetryable(format!("Breaking schema drift: {}", drift.summary()))))
+                    .context(format!("Worker: Schema check failed for task {} in job {}", i + 1, job_run.job_id));
+            }
+        }
+
+        let df = if transforms.is_empty() {
+            df
+        } else {
+            info!("Worker: Applying {} transform step(s) for task {} in job {}.", transforms.len(), i + 1, job_run.job_id);
+            let (df, stats) = tokio::task::spawn_blocking(move || apply_transforms(df, &transforms))
+                .await
+                .context("Transform task panicked")?
+                .context(format!("Worker: Transform failed for task {} in job {}", i + 1, job_run.job_id))?;
+            if let Some(dropped) = stats.rows_deduplicated {
+                info!("Worker: Dropped {} duplicate rows for task {} in job {}.", dropped, i + 1, job_run.job_id);
+            }
+            task_metrics.rows_transformed = Some(df.height());
+            task_metrics.rows_deduplicated = stats.rows_deduplicated;
+            df
+        };
+
+        if !checks.is_empty() {
+            info!("Worker: Running {} data quality check(s) for task {} in job {}.", checks.len(), i + 1, job_run.job_id);
+            let mut failed_errors = Vec::new();
+            for (check_order, outcome) in run_checks(&df, &checks).into_iter().enumerate() {
+                let label = match &outcome.column_name {
+                    Some(column) => format!("{}({})", outcome.check_name, column),
+                    None => outcome.check_name.clone(),
+                };
+                if !outcome.passed {
+                    match outcome.severity {
+                        Severity::Error => {
+                            error!("Worker: Check {} failed for task {} in job {}: {}", label, i + 1, job_run.job_id, outcome.message);
+                            failed_errors.push(format!("{}: {}", label, outcome.message));
+                        }
+                        Severity::Warn => warn!("Worker: Check {} failed for task {} in job {}: {}", label, i + 1, job_run.job_id, outcome.message),
+                    }
+                }
+                let result = NewCheckResult {
+                    run_id: job_run.run_id,
+                    job_id: job_run.job_id,
+                    task_order: task.task_order,
+                    check_order: check_order as i32 + 1,
+                    check_name: outcome.check_name,
+                    column_name: outcome.column_name,
+                    severity: outcome.severity.as_str().to_string(),
+                    passed: outcome.passed,
+                    failing_rows: outcome.failing_rows,
+                    message: outcome.message,
+                };
+                db.record_check_result(&result).await.context(format!("Worker: Failed to record check result for task {} in job {}", i + 1, job_run.job_id))?;
+            }
+            if !failed_errors.is_empty() {
+                return Err(anyhow::Error::new(NonRetryable(format!("Data quality checks failed: {}", failed_errors.join("; ")))))
+                    .context(format!("Worker: Checks failed for task {} in job {}", i + 1, job_run.job_id));
+            }
+        }
+
+        let df = if masking.is_empty() {
+            df
+        } else {
+            info!("Worker: Masking {} column(s) for task {} in job {}.", masking.len(), i + 1, job_run.job_id);
+            task_metrics.masking = masking.iter().map(|p| p.audit()).collect();
+            let mut salts = HashMap::new();
+            for name in masking.iter().filter_map(|p| p.salt_secret()) {
+                let salt = secrets.get(name).await.context(format!("Worker: Masking failed for task {} in job {}", i + 1, job_run.job_id))?;
+                run_log.add_secret(&salt);
+                salts.insert(name.to_string(), salt);
+            }
+            tokio::task::spawn_blocking(move || apply_masking(df, &masking, &salts))
+                .await
+                .context("Masking task panicked")?
+                .context(format!("Worker: Masking failed for task {} in job {}", i + 1, job_run.job_id))?
+        };
+        // Loaders that delivered on an earlier attempt keep that attempt's result.
+        let (done, loaders): (Vec<LoaderSpec>, Vec<LoaderSpec>) = loaders.into_iter().partition(|l| delivered.contains_key(&(task.task_order, l.index)));
+        for spec in done {
+            info!("Worker: Skipping loader {} ({}) for task {} in job {}, which delivered on an earlier attempt.", spec.index + 1, spec.loader_type, i + 1, job_run.job_id);
+            task_metrics.loaders.push(delivered[&(task.task_order, spec.index)].clone());
+        }
+        info!("Worker: Loading data for task {} in job {} into {} loader(s).", i + 1, job_run.job_id, loaders.len());
+        let fingerprint = schema_hash(&df.schema());
+        let outcomes = run_loaders(loaders, df).await.context(format!("Worker: Loading failed for task {} in job {}", i + 1, job_run.job_id))?;
 
-        info!("Worker: Loading data for task {} in job {}.", i + 1, job_run.job_id);
-        loader.load(df).await.context(format!("Worker: Loading failed for task {} in job {}", i + 1, job_run.job_id))?;
+        let mut required_failure = None;
+        for outcome in outcomes {
+            let duration_ms = outcome.duration.as_millis() as i64;
+            // A partial failure still delivered some data, which is recorded like a success.
+            let (report, error) = match outcome.result {
+                Ok(report) => (Some(report), None),
+                Err(e) => match e.downcast::<PartialLoadError>() {
+                    Ok(partial) => {
+                        let error = if partial.retryable { anyhow::anyhow!(partial.message) } else { anyhow::Error::new(NonRetryable(partial.message)) };
+                        (Some(partial.report), Some(error))
+                    }
+                    Err(e) => (None, Some(e)),
+                },
+            };
+
+            let loader_metrics = match &report {
+                Some(report) => LoaderMetrics::from_report(outcome.index, &outcome.loader_type, outcome.required, duration_ms, report, error.as_ref().map(|e| format!("{:#}", e))),
+                None => LoaderMetrics::failed(outcome.index, &outcome.loader_type, outcome.required, duration_ms, error.as_ref().map(|e| format!("{:#}", e)).unwrap_or_default()),
+            };
+            if error.is_none() {
+                delivered.insert((task.task_order, outcome.index), loader_metrics.clone());
+            }
+            task_metrics.loaders.push(loader_metrics);
+
+            if let Some(report) = report.filter(|r| error.is_none() || r.row_count > 0) {
+                info!("Worker: Loader {} ({}) for task {} in job {} wrote {} rows to {}.", outcome.index + 1, outcome.loader_type, i + 1, job_run.job_id, report.row_count, report.target);
+                let result = NewJobResult {
+                    job_id: job_run.job_id,
+                    run_id: job_run.run_id,
+                    task_order: task.task_order,
+                    loader_index: outcome.index as i32,
+                    loader_type: outcome.loader_type.clone(),
+                    warehouse_table: report.target,
+                    file_path: report.file_path,
+                    row_count: report.row_count,
+                    bytes_written: report.bytes_written,
+                    duration_ms,
+                    schema_hash: fingerprint.clone(),
+                };
+                db.record_job_result(&result).await.context(format!("Worker: Failed to record result for task {} in job {}", i + 1, job_run.job_id))?;
+            }
+
+            match error {
+                Some(e) if outcome.required => {
+                    error!("Worker: Required loader {} ({}) for task {} in job {} failed: {}", outcome.index + 1, outcome.loader_type, i + 1, job_run.job_id, run_log.redact(&format!("{:?}", e)));
+                    required_failure.get_or_insert(e.context(format!("Loader {} ({}) failed", outcome.index + 1, outcome.loader_type)));
+                }
+                Some(e) => {
+                    warn!("Worker: Optional loader {} ({}) for task {} in job {} failed: {}", outcome.index + 1, outcome.loader_type, i + 1, job_run.job_id, run_log.redact(&format!("{:?}", e)));
+                }
+                None => {}
+            }
+        }
+        task_metrics.loaders.sort_by_key(|l| l.index);
+        if let Some(e) = required_failure {
+            return Err(e).context(format!("Worker: Loading failed for task {} in job {}", i + 1, job_run.job_id));
+        }
         info!("Worker: Data loaded for task {} in job {}.", i + 1, job_run.job_id);
     }
 
@@ -81,45 +401,128 @@ async fn execute_job(db: &Db, job_run: &JobRun) -> Result<()> {
     Ok(())
 }
 
-fn get_extractor(config: &Value) -> Result<Arc<dyn Extractor + Send + Sync>> {
-    let extractor_type = config["type"].as_str().context("Extractor type not specified")?;
-    debug!("Worker: Getting extractor of type: {}", extractor_type);
-    match extractor_type {
-        "api" => {
-            let url = config["url"].as_str().context("URL not specified for API extractor")?;
-            debug!("Worker: Created API extractor for URL: {}", url);
-            Ok(Arc::new(ApiExtractor { url: url.to_string() }))
-        }
-        "csv" => {
-            let path = config["path"].as_str().context("Path not specified for CSV extractor")?;
-            debug!("Worker: Created CSV extractor for path: {}", path);
-            Ok(Arc::new(CsvExtractor { path: path.to_string() }))
-        }
-        "parquet" => {
-            let path = config["path"].as_str().context("Path not specified for Parquet extractor")?;
-            debug!("Worker: Created Parquet extractor for path: {}", path);
-            Ok(Arc::new(ParquetExtractor { path: path.to_string() }))
-        }
-        _ => {
-            error!("Worker: Unsupported extractor type: {}", extractor_type);
-            Err(anyhow::anyhow!("Unsupported extractor type: {}", extractor_type))
-        }
+/// A loader configured for a task, with the fan-out settings from its config entry.
+struct LoaderSpec {
+    index: usize,
+    loader_type: String,
+    /// Whether a failure of this loader fails the task.
+    required: bool,
+    /// Loaders sharing a destination file are run one after another rather than concurrently.
+    lock_key: Option<String>,
+    loader: Arc<dyn Loader + Send + Sync>,
+}
+
+struct LoaderOutcome {
+    index: usize,
+    loader_type: String,
+    required: bool,
+    duration: Duration,
+    result: Result<LoadReport>,
+}
+
+/// Merges the named connections into a task's extractor and loader configs, then replaces the
+/// secret references and template expressions in its configs with their values, for this
+/// attempt only. Secrets are resolved first, so values substituted by the templates (such as
+/// run parameters) can't reference secrets.
+///
+/// A missing connection, secret or run parameter would be just as missing on the next attempt,
+/// so these failures are `NonRetryable`.
+async fn resolve_configs(
+    secrets: &SecretStore,
+    connections: &Connections,
+    run_context: &RunContext,
+    task: &mut TaskDefinition,
+    run_log: &RunLog,
+) -> Result<()> {
+    let permanent = |e: anyhow::Error| anyhow::Error::new(NonRetryable(format!("{:#}", e)));
+    task.extractor_config = connections::apply(&task.extractor_config, connections).context("Invalid extractor_config").map_err(permanent)?;
+    task.loader_config = connections::apply(&task.loader_config, connections).context("Invalid loader_config").map_err(permanent)?;
+    for config in task.configs_mut() {
+        // Only failing to read the secret store's table is worth another attempt.
+        let resolved = secrets
+            .resolve(config, run_log)
+            .await
+            .map_err(|e| if e.chain().any(|cause| cause.is::<sqlx::Error>()) { e } else { permanent(e) })?;
+        *config = run_context.render(&resolved).map_err(permanent)?;
     }
+    Ok(())
 }
 
-fn get_loader(config: &Value) -> Result<Arc<dyn Loader + Send + Sync>> {
-    let loader_type = config["type"].as_str().context("Loader type not specified")?;
-    debug!("Worker: Getting loader of type: {}", loader_type);
-    match loader_type {
-        "duckdb" => {
-            let db_path = config["db_path"].as_str().context("db_path not specified for DuckDB loader")?;
-            let table_name = config["table_name"].as_str().context("table_name not specified for DuckDB loader")?;
-            debug!("Worker: Created DuckDB loader for path: {} and table: {}", db_path, table_name);
-            Ok(Arc::new(DuckDBLoader::new(db_path, table_name)))
-        }
-        _ => {
-            error!("Worker: Unsupported loader type: {}", loader_type);
-            Err(anyhow::anyhow!("Unsupported loader type: {}", loader_type))
+/// Redacts secret values from every string in `value`.
+fn redact_value(run_log: &RunLog, value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(run_log.redact(&text)),
+        Value::Array(items) => Value::Array(items.into_iter().map(|v| redact_value(run_log, v)).collect()),
+        Value::Object(fields) => Value::Object(fields.into_iter().map(|(k, v)| (k, redact_value(run_log, v))).collect()),
+        other => other,
+    }
+}
+
+/// Builds the loaders for a task. `loader_config` is either a single loader object or a list of them.
+fn get_loaders(config: &Value, ctx: &TaskContext) -> Result<Vec<LoaderSpec>> {
+    let configs = match config {
+        Value::Array(items) if items.is_empty() => return Err(anyhow::anyhow!("loader_config list is empty")),
+        Value::Array(items) => items.iter().collect::<Vec<_>>(),
+        single => vec![single],
+    };
+    configs
+        .into_iter()
+        .enumerate()
+        .map(|(index, c)| {
+            let loader = registry().loader(c, ctx).context(format!("Invalid loader config at position {}", index + 1))?;
+            Ok(LoaderSpec {
+                index,
+                loader_type: c["type"].as_str().unwrap_or_default().to_string(),
+                required: c["required"].as_bool().unwrap_or(true),
+                lock_key: c["db_path"].as_str().or(c["path"].as_str()).map(str::to_string),
+                loader,
+            })
+        })
+        .collect()
+}
+
+/// Hands the extracted frame to every loader. Loaders run concurrently, except that loaders
+/// writing to the same file are serialized. Outcomes are returned in configuration order.
+async fn run_loaders(specs: Vec<LoaderSpec>, df: DataFrame) -> Result<Vec<LoaderOutcome>> {
+    let mut groups: Vec<Vec<LoaderSpec>> = Vec::new();
+    let mut group_by_key: HashMap<String, usize> = HashMap::new();
+    for spec in specs {
+        match spec.lock_key.clone() {
+            Some(key) => match group_by_key.get(&key) {
+                Some(&g) => groups[g].push(spec),
+                None => {
+                    group_by_key.insert(key, groups.len());
+                    groups.push(vec![spec]);
+                }
+            },
+            None => groups.push(vec![spec]),
         }
     }
+
+    let mut set = JoinSet::new();
+    for group in groups {
+        let df = df.clone();
+        set.spawn(async move {
+            let mut outcomes = Vec::with_capacity(group.len());
+            for spec in group {
+                let started = Instant::now();
+                let result = spec.loader.load(df.clone()).await;
+                outcomes.push(LoaderOutcome {
+                    index: spec.index,
+                    loader_type: spec.loader_type,
+                    required: spec.required,
+                    duration: started.elapsed(),
+                    result,
+                });
+            }
+            outcomes
+        });
+    }
+
+    let mut outcomes = Vec::new();
+    while let Some(joined) = set.join_next().await {
+        outcomes.extend(joined.context("Loader task panicked")?);
+    }
+    outcomes.sort_by_key(|o| o.index);
+    Ok(outcomes)
 }
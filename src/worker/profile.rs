@@ -0,0 +1,143 @@
+//! Column profiling of extracted data.
+//!
+//! For every task the worker profiles the extracted frame and stores one `ColumnProfile` per
+//! column in the run's metrics, so trends and anomalies in the data itself can be charted from
+//! the run records without querying the destination.
+
+use anyhow::Result;
+use polars::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Number of most frequent values kept per column.
+pub const TOP_VALUES: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TopValue {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub dtype: String,
+    pub null_count: usize,
+    /// Distinct non-null values.
+    pub distinct_count: usize,
+    /// Smallest and largest non-null value: numbers for numeric columns, text otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<Value>,
+    /// Only for numeric columns.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean: Option<f64>,
+    /// Most frequent non-null values, most frequent first, ties by value.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub top_values: Vec<TopValue>,
+}
+
+impl ColumnProfile {
+    /// Drops everything that reveals values, keeping only the counts. Used for masked columns,
+    /// which are profiled before masking is applied.
+    pub fn redacted(mut self) -> Self {
+        self.min = None;
+        self.max = None;
+        self.mean = None;
+        self.top_values.clear();
+        self
+    }
+}
+
+fn profile_column(series: &Series) -> Result<ColumnProfile> {
+    let text = series.cast(&DataType::String)?;
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for value in text.str()?.into_iter().flatten() {
+        *counts.entry(value).or_default() += 1;
+    }
+    let mut top_values: Vec<TopValue> = counts
+        .iter()
+        .map(|(value, count)| TopValue { value: value.to_string(), count: *count })
+        .collect();
+    top_values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    top_values.truncate(TOP_VALUES);
+
+    let (min, max, mean) = if series.dtype().is_numeric() {
+        let numbers = series.cast(&DataType::Float64)?;
+        let numbers = numbers.f64()?;
+        (numbers.min().map(Value::from), numbers.max().map(Value::from), numbers.mean())
+    } else {
+        (
+            counts.keys().min().map(|v| Value::from(*v)),
+            counts.keys().max().map(|v| Value::from(*v)),
+            None,
+        )
+    };
+
+    Ok(ColumnProfile {
+        name: series.name().to_string(),
+        dtype: series.dtype().to_string(),
+        null_count: series.null_count(),
+        distinct_count: counts.len(),
+        min,
+        max,
+        mean,
+        top_values,
+    })
+}
+
+/// Profiles every column of the frame, in column order. Columns listed in `redact` keep only
+/// their null and distinct counts.
+pub fn profile_frame(df: &DataFrame, redact: &[&str]) -> Result<Vec<ColumnProfile>> {
+    df.get_columns()
+        .iter()
+        .map(|series| {
+            let profile = profile_column(series)?;
+            Ok(if redact.contains(&series.name()) { profile.redacted() } else { profile })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_numeric_and_text_columns() {
+        let df = df!(
+            "amount" => &[Some(1.0f64), Some(3.0), None, Some(3.0)],
+            "city" => &[Some("Oslo"), Some("Bergen"), Some("Oslo"), None]
+        )
+        .unwrap();
+
+        let profiles = profile_frame(&df, &[]).unwrap();
+
+        let amount = &profiles[0];
+        assert_eq!((amount.null_count, amount.distinct_count), (1, 2));
+        assert_eq!((amount.min.clone(), amount.max.clone()), (Some(Value::from(1.0)), Some(Value::from(3.0))));
+        assert_eq!(amount.mean, Some(7.0 / 3.0));
+        let city = &profiles[1];
+        assert_eq!((city.null_count, city.distinct_count, city.mean), (1, 2, None));
+        assert_eq!((city.min.clone(), city.max.clone()), (Some(Value::from("Bergen")), Some(Value::from("Oslo"))));
+        assert_eq!(
+            city.top_values,
+            vec![
+                TopValue { value: "Oslo".to_string(), count: 2 },
+                TopValue { value: "Bergen".to_string(), count: 1 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redacted_columns_keep_only_counts() {
+        let df = df!("email" => &["a@x.io", "b@x.io", "a@x.io"]).unwrap();
+
+        let profile = &profile_frame(&df, &["email"]).unwrap()[0];
+
+        assert_eq!((profile.null_count, profile.distinct_count), (0, 2));
+        assert!(profile.min.is_none() && profile.max.is_none() && profile.top_values.is_empty());
+        assert_eq!(serde_json::to_value(profile).unwrap(), serde_json::json!({ "name": "email", "dtype": "str", "null_count": 0, "distinct_count": 2 }));
+    }
+}
@@ -1,14 +1,18 @@
 // In tests/common/mod.rs
 
+// Each test binary includes this module but uses only some of its helpers.
+#![allow(dead_code)]
+
 use anyhow::Result;
 use orc_rust_ator::orchestrator::scheduler::Scheduler;
 use orc_rust_ator::orchestrator::worker_manager::WorkerManager;
 use orc_rust_ator::state::db::Db;
 use std::env;
+use tokio::time::{sleep, Duration};
 
 pub async fn setup() -> Result<String> {
     // Initialize the global logger.
-    let _ = orc_rust_ator::logger::initialize_logger();
+    orc_rust_ator::logger::initialize_logger();
 
     // 1. Set the database URL to an in-memory SQLite database for testing.
     let database_url = "sqlite::memory:";
@@ -44,3 +48,27 @@ pub async fn setup() -> Result<String> {
 
     Ok(server_url)
 }
+
+/// Registers a user and logs in, returning a bearer token for the protected endpoints.
+pub async fn login(client: &reqwest::Client, server_url: &str) -> Result<String> {
+    let credentials = serde_json::json!({ "username": "admin", "password": "admin-password" });
+    client.post(format!("{}/auth/register", server_url)).json(&credentials).send().await?.error_for_status()?;
+    let res = client.post(format!("{}/auth/login", server_url)).json(&credentials).send().await?.error_for_status()?;
+    let body: serde_json::Value = res.json().await?;
+    Ok(body["token"].as_str().unwrap_or_default().to_string())
+}
+
+/// Polls `/runs` until the job's first run finishes and returns it. Failing runs are retried
+/// by the worker, so this waits long enough for all attempts.
+pub async fn wait_for_finished_run(client: &reqwest::Client, server_url: &str, job_id: &str) -> Result<serde_json::Value> {
+    for _ in 0..60 {
+        let runs: Vec<serde_json::Value> = client.get(format!("{}/runs", server_url)).send().await?.json().await?;
+        if let Some(r) = runs.into_iter().find(|r| r["job_id"] == job_id) {
+            if r["status"] == "success" || r["status"] == "failed" {
+                return Ok(r);
+            }
+        }
+        sleep(Duration::from_millis(500)).await;
+    }
+    anyhow::bail!("Run for job {} did not finish", job_id)
+}
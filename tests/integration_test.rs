@@ -1,18 +1,19 @@
 use anyhow::Result;
-use reqwest;
 use serde_json::json;
-use tokio;
+use sqlx::Connection;
 use tokio::time::{sleep, Duration};
 
 mod common;
 
+use common::wait_for_finished_run;
+
 #[tokio::test]
 async fn test_health_check() -> Result<()> {
     let server_url = common::setup().await?;
 
     // Make a request to the /health endpoint.
     let client = reqwest::Client::new();
-    let res = client.get(&format!("{}/health", server_url)).send().await?;
+    let res = client.get(format!("{}/health", server_url)).send().await?;
 
     // Assert that the request was successful.
     assert!(res.status().is_success());
@@ -20,6 +21,23 @@ async fn test_health_check() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_list_plugins() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+
+    let plugins: Vec<serde_json::Value> = client.get(format!("{}/plugins", server_url)).send().await?.json().await?;
+
+    let names: Vec<(&str, &str)> = plugins.iter().map(|p| (p["kind"].as_str().unwrap(), p["name"].as_str().unwrap())).collect();
+    assert!(names.contains(&("extractor", "csv")));
+    assert!(names.contains(&("transform", "dedupe")));
+    assert!(names.contains(&("loader", "duckdb")));
+    let duckdb = plugins.iter().find(|p| p["kind"] == "loader" && p["name"] == "duckdb").unwrap();
+    assert_eq!(duckdb["config_schema"]["required"], json!(["db_path", "table_name"]));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_create_and_run_job_lifecycle() -> Result<()> {
     let server_url = common::setup().await?;
@@ -47,7 +65,7 @@ async fn test_create_and_run_job_lifecycle() -> Result<()> {
     });
 
     let res = client
-        .post(&format!("{}/jobs", server_url))
+        .post(format!("{}/jobs", server_url))
         .json(&create_job_payload)
         .send()
         .await?;
@@ -58,32 +76,897 @@ async fn test_create_and_run_job_lifecycle() -> Result<()> {
 
     // 2. Manually trigger the job
     let res = client
-        .post(&format!("{}/jobs/{}/run", server_url, job_id))
+        .post(format!("{}/jobs/{}/run", server_url, job_id))
+        .send()
+        .await?;
+    assert!(res.status().is_success());
+
+    // 3. Wait for the run to finish and assert it succeeded
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success");
+
+    // 4. The loader's lineage is recorded against the job (the every-second schedule may add runs)
+    let res = client
+        .get(format!("{}/jobs/{}/results", server_url, job_id))
+        .send()
+        .await?;
+    let results: Vec<serde_json::Value> = res.json().await?;
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| r["warehouse_table"] == "test_output" && r["loader_type"] == "duckdb"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fan_out_to_multiple_loaders() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+    let export_dir = tempfile::tempdir()?;
+    let ndjson_path = export_dir.path().join("users_{{ run_id }}.ndjson");
+
+    // One extraction feeds three loaders; the optional SQLite loader points at an unwritable path.
+    let create_job_payload = json!({
+        "job_name": "Fan-out CSV",
+        "description": "Loads one extraction into several destinations",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [
+            {
+                "extractor_config": {
+                    "type": "csv",
+                    "path": "test_data.csv"
+                },
+                "loader_config": [
+                    { "type": "duckdb", "db_path": ":memory:", "table_name": "fan_out" },
+                    { "type": "ndjson", "path": ndjson_path.to_str().unwrap() },
+                    { "type": "sqlite", "db_path": "/nonexistent/dir/out.sqlite", "table_name": "fan_out", "required": false }
+                ]
+            }
+        ]
+    });
+
+    let res = client.post(format!("{}/jobs", server_url)).json(&create_job_payload).send().await?;
+    assert!(res.status().is_success());
+    let job_response: serde_json::Value = res.json().await?;
+    let job_id = job_response["job_id"].as_str().unwrap().to_string();
+
+    let res = client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    assert!(res.status().is_success());
+
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success");
+
+    // Only the two loaders that succeeded leave lineage behind.
+    let results: Vec<serde_json::Value> = client
+        .get(format!("{}/runs/{}/results", server_url, run["run_id"].as_str().unwrap()))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let loader_types: Vec<&str> = results.iter().map(|r| r["loader_type"].as_str().unwrap()).collect();
+    assert_eq!(loader_types.len(), 2);
+    assert!(loader_types.contains(&"duckdb"));
+    assert!(loader_types.contains(&"ndjson"));
+    let run_id = run["run_id"].as_str().unwrap();
+    assert!(export_dir.path().join(format!("users_{}.ndjson", run_id)).exists());
+
+    // The run record keeps the outcome of every loader, including the failed optional one.
+    let loaders = run["metrics"]["tasks"][0]["loaders"].as_array().unwrap();
+    let statuses: Vec<&str> = loaders.iter().map(|l| l["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses, vec!["success", "success", "failed"]);
+    assert!(loaders[2]["error"].is_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transform_steps_applied_before_loading() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+
+    let mut payload = json!({
+        "job_name": "Transformed CSV",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [
+            {
+                "extractor_config": { "type": "csv", "path": "test_data.csv" },
+                "transform": [{ "op": "cast", "columns": { "id": "money" } }],
+                "loader_config": { "type": "duckdb", "db_path": ":memory:", "table_name": "transformed" }
+            }
+        ]
+    });
+
+    // Invalid steps are rejected when the job is created, with the offending field.
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    let body = res.json::<serde_json::Value>().await?;
+    assert_eq!(body["errors"][0]["field"], "tasks[0].transform[0].columns");
+
+    payload["tasks"][0]["transform"] = json!([
+        { "op": "filter", "expr": "id > 1" },
+        { "op": "derive", "column": "label", "expr": "upper(name)" },
+        { "op": "dedupe", "keys": ["id"] }
+    ]);
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success");
+    assert_eq!(run["metrics"]["tasks"][0]["rows_extracted"], 2);
+    assert_eq!(run["metrics"]["tasks"][0]["rows_transformed"], 1);
+    assert_eq!(run["metrics"]["tasks"][0]["rows_deduplicated"], 0);
+
+    // Column profiles describe the extracted data, before any transform step.
+    let profile = &run["metrics"]["tasks"][0]["profile"];
+    assert_eq!(profile[0]["name"], "id");
+    assert_eq!((profile[0]["min"].as_f64(), profile[0]["max"].as_f64()), (Some(1.0), Some(2.0)));
+    assert_eq!(profile[1]["distinct_count"], 2);
+    assert_eq!(profile[1]["null_count"], 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_profile_hides_masked_column_renamed_by_transform() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+
+    // The masking policy names the column as renamed by the transform.
+    let payload = json!({
+        "job_name": "Renamed and masked CSV",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [{
+            "extractor_config": { "type": "csv", "path": "test_data.csv" },
+            "transform": [{ "op": "rename", "columns": { "name": "customer" } }],
+            "masking": [{ "column": "customer", "method": "redact", "keep_first": 2 }],
+            "loader_config": { "type": "duckdb", "db_path": ":memory:", "table_name": "masked" }
+        }]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success", "{}", run["error_message"]);
+
+    let profile = run["metrics"]["tasks"][0]["profile"].as_array().unwrap();
+    let names: Vec<&str> = profile.iter().map(|c| c["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["id", "name"]);
+    assert_eq!(profile[1]["null_count"], 0);
+    assert!(profile[1].get("top_values").is_none() && profile[1].get("min").is_none());
+    assert!(!run["metrics"].to_string().contains("test_name"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_data_quality_checks_recorded_and_enforced() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+
+    let mut payload = json!({
+        "job_name": "Checked CSV",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [
+            {
+                "extractor_config": { "type": "csv", "path": "test_data.csv" },
+                "checks": [
+                    { "check": "not_null", "column": "id" },
+                    { "check": "row_count", "min": 5, "severity": "warn" }
+                ],
+                "loader_config": { "type": "duckdb", "db_path": ":memory:", "table_name": "checked" }
+            }
+        ]
+    });
+
+    // A failed warn check is recorded but the data is still loaded.
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success");
+
+    let run_id = run["run_id"].as_str().unwrap();
+    let checks: Vec<serde_json::Value> = client.get(format!("{}/runs/{}/checks", server_url, run_id)).send().await?.json().await?;
+    let outcomes: Vec<(&str, bool)> = checks.iter().map(|c| (c["check_name"].as_str().unwrap(), c["passed"].as_bool().unwrap())).collect();
+    assert_eq!(outcomes, vec![("not_null", true), ("row_count", false)]);
+
+    // A failed error check fails the run before anything is loaded.
+    payload["job_name"] = json!("Strictly Checked CSV");
+    payload["tasks"][0]["checks"] = json!([{ "check": "accepted_values", "column": "name", "values": ["test_name_1"] }]);
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "failed");
+    assert!(run["error_message"].as_str().unwrap().contains("Checks failed"));
+
+    // Failed checks aren't retried: the run ends on its first attempt, with each check recorded once.
+    assert_eq!(run["metrics"]["attempt"], 1);
+    let checks: Vec<serde_json::Value> = client
+        .get(format!("{}/runs/{}/checks", server_url, run["run_id"].as_str().unwrap()))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let outcomes: Vec<(&str, bool)> = checks.iter().map(|c| (c["check_name"].as_str().unwrap(), c["passed"].as_bool().unwrap())).collect();
+    assert_eq!(outcomes, vec![("accepted_values", false)]);
+
+    let results: Vec<serde_json::Value> = client
+        .get(format!("{}/runs/{}/results", server_url, run["run_id"].as_str().unwrap()))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert!(results.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_process_extractor_output_and_logs() -> Result<()> {
+    use polars::prelude::*;
+
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+    let dir = tempfile::tempdir()?;
+    let stream_path = dir.path().join("users.arrow");
+    let mut df = df!("id" => [1i64, 2, 3], "name" => ["a", "b", "c"])?;
+    IpcStreamWriter::new(std::fs::File::create(&stream_path)?).finish(&mut df)?;
+
+    // The program reads its config from stdin, reports progress on stderr and emits the stream.
+    let script = r#"read -r doc; echo "extracting for $0" >&2; cat "$1""#;
+    let payload = json!({
+        "job_name": "Process extractor",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [
+            {
+                "extractor_config": {
+                    "type": "process",
+                    "command": "sh",
+                    "args": ["-c", script, "users", stream_path.to_str().unwrap()],
+                    "config": { "table": "users" }
+                },
+                "loader_config": { "type": "duckdb", "db_path": ":memory:", "table_name": "from_process" }
+            }
+        ]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success");
+    assert_eq!(run["metrics"]["tasks"][0]["rows_extracted"], 3);
+
+    let run_id = run["run_id"].as_str().unwrap();
+    let logs: Vec<serde_json::Value> = client.get(format!("{}/runs/{}/logs", server_url, run_id)).send().await?.json().await?;
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0]["stream"], "stderr");
+    assert_eq!(logs[0]["message"], "extracting for users");
+
+    // Stdin carries a process loader's data, so the config can't be sent there.
+    let mut invalid = payload.clone();
+    invalid["tasks"][0]["loader_config"] = json!({ "type": "process", "command": "true", "config_via": "stdin" });
+    let res = client.post(format!("{}/jobs", server_url)).json(&invalid).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    let body = res.json::<serde_json::Value>().await?;
+    assert_eq!(body["errors"][0]["field"], "tasks[0].loader_config.config_via");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_task_runs_after_load() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+
+    let mut payload = json!({
+        "job_name": "Load then command",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [
+            {
+                "extractor_config": { "type": "csv", "path": "test_data.csv" },
+                "loader_config": { "type": "duckdb", "db_path": ":memory:", "table_name": "before_command" }
+            },
+            {
+                "command": {
+                    "command": "sh",
+                    "args": ["-c", "echo \"refreshing $MODEL\"; exit 1"],
+                    "env": { "MODEL": "users" },
+                    "warn_exit_codes": [1]
+                }
+            }
+        ]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success");
+    let command = &run["metrics"]["tasks"][1]["command"];
+    assert_eq!((command["exit_code"].as_i64(), command["status"].as_str()), (Some(1), Some("warn")));
+
+    let run_id = run["run_id"].as_str().unwrap();
+    let logs: Vec<serde_json::Value> = client.get(format!("{}/runs/{}/logs", server_url, run_id)).send().await?.json().await?;
+    assert_eq!(logs.len(), 1);
+    assert_eq!((logs[0]["task_order"].as_i64(), logs[0]["stream"].as_str()), (Some(2), Some("stdout")));
+    assert_eq!(logs[0]["message"], "refreshing users");
+
+    // Any other exit code fails the run, with the command's last stderr line in the error.
+    payload["job_name"] = json!("Failing command");
+    payload["tasks"][1]["command"]["args"] = json!(["-c", "echo 'model users not found' >&2; exit 3"]);
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "failed");
+    assert!(run["error_message"].as_str().unwrap().contains("exit code 3: model users not found"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retried_run_skips_finished_command_tasks() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+    let dir = tempfile::tempdir()?;
+    let counter = dir.path().join("runs").to_string_lossy().into_owned();
+    let marker = dir.path().join("attempted").to_string_lossy().into_owned();
+
+    // The second command fails on the first attempt only, which makes the worker retry the run.
+    let payload = json!({
+        "job_name": "Retried commands",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [
+            { "command": { "command": "sh", "args": ["-c", format!("echo run >> '{}'", counter)] } },
+            { "command": { "command": "sh", "args": ["-c", format!("[ -f '{0}' ] && exit 0; touch '{0}'; exit 1", marker)] } }
+        ]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success", "{}", run["error_message"]);
+    assert!(std::path::Path::new(&marker).exists());
+
+    // The first command finished on the first attempt, so the retry didn't run it again.
+    assert_eq!(std::fs::read_to_string(&counter)?, "run\n");
+    assert_eq!(run["metrics"]["tasks"][0]["command"]["exit_code"], 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sql_task_builds_models_from_loaded_data() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("warehouse.db").to_string_lossy().into_owned();
+    let models = dir.path().join("models.sql");
+    std::fs::write(
+        &models,
+        "CREATE SCHEMA IF NOT EXISTS analytics;\n\
+         CREATE OR REPLACE TABLE analytics.posts (id BIGINT, name VARCHAR);\n\
+         INSERT INTO analytics.posts SELECT id, upper(name) FROM raw_api_posts;\n\
+         DELETE FROM analytics.posts WHERE id = 1;\n",
+    )?;
+
+    let payload = json!({
+        "job_name": "Load then model",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [
+            {
+                "extractor_config": { "type": "csv", "path": "test_data.csv" },
+                "loader_config": { "type": "duckdb", "db_path": db_path, "table_name": "raw_api_posts" }
+            },
+            { "sql": { "db_path": db_path, "file": models.to_string_lossy() } }
+        ]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success", "{}", run["error_message"]);
+    let rows: Vec<i64> = run["metrics"]["tasks"][1]["sql"]["statements"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["rows_affected"].as_i64().unwrap())
+        .collect();
+    let loaded = run["metrics"]["tasks"][0]["rows_extracted"].as_i64().unwrap();
+    assert_eq!(rows, vec![0, 0, loaded, 1]);
+
+    // A SQL task can't also extract.
+    let mut invalid = payload.clone();
+    invalid["tasks"][1]["extractor_config"] = json!({ "type": "csv", "path": "test_data.csv" });
+    let res = client.post(format!("{}/jobs", server_url)).json(&invalid).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retried_run_skips_finished_sql_tasks() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("warehouse.db").to_string_lossy().into_owned();
+    let marker = dir.path().join("attempted").to_string_lossy().into_owned();
+
+    // The command fails on the first attempt only, which makes the worker retry the run.
+    let payload = json!({
+        "job_name": "Retried SQL",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [
+            { "sql": { "db_path": db_path, "statements": ["CREATE TABLE IF NOT EXISTS events (id BIGINT)", "INSERT INTO events VALUES (1)"] } },
+            { "command": { "command": "sh", "args": ["-c", format!("[ -f '{0}' ] && exit 0; touch '{0}'; exit 1", marker)] } }
+        ]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success", "{}", run["error_message"]);
+    assert!(std::path::Path::new(&marker).exists());
+
+    // The SQL task committed on the first attempt, so the retry didn't insert the row again.
+    let conn = duckdb::Connection::open(&db_path)?;
+    let rows: i64 = conn.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))?;
+    assert_eq!(rows, 1);
+    assert_eq!(run["metrics"]["tasks"][0]["sql"]["statements"][1]["rows_affected"], 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_secret_references_are_resolved_and_redacted() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+    std::env::set_var("SECRETS_KEY", "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=");
+
+    // Managing secrets takes a token.
+    let res = client.get(format!("{}/secrets", server_url)).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let token = common::login(&client, &server_url).await?;
+
+    let res = client
+        .put(format!("{}/secrets/ORC_IT_API_TOKEN", server_url))
+        .bearer_auth(&token)
+        .json(&json!({ "value": "tok-8f3a" }))
         .send()
         .await?;
     assert!(res.status().is_success());
+    let secrets: Vec<serde_json::Value> = client.get(format!("{}/secrets", server_url)).bearer_auth(&token).send().await?.json().await?;
+    assert_eq!(secrets.len(), 1);
+    assert_eq!(secrets[0]["name"], "ORC_IT_API_TOKEN");
+    assert!(secrets[0].get("ciphertext").is_none());
+
+    let payload = json!({
+        "job_name": "Command with a secret",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [{
+            "command": {
+                "command": "sh",
+                "args": ["-c", "echo \"calling with $TOKEN\""],
+                "env": { "TOKEN": "${secret:ORC_IT_API_TOKEN}" }
+            }
+        }]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+    let job = client.get(format!("{}/jobs/{}", server_url, job_id)).send().await?.text().await?;
+    assert!(job.contains("${secret:ORC_IT_API_TOKEN}"));
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success", "{}", run["error_message"]);
+    let run_id = run["run_id"].as_str().unwrap();
+    let logs: Vec<serde_json::Value> = client.get(format!("{}/runs/{}/logs", server_url, run_id)).send().await?.json().await?;
+    assert_eq!(logs[0]["message"], "calling with ***");
+
+    // References are checked when the job is created.
+    let mut invalid = payload.clone();
+    invalid["tasks"][0]["command"]["env"]["TOKEN"] = json!("${secret:ORC_IT_API_TOKEN");
+    let res = client.post(format!("{}/jobs", server_url)).json(&invalid).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = res.json().await?;
+    assert_eq!(body["errors"][0]["field"], "tasks[0].command.env.TOKEN");
 
-    // 3. Poll for the job run status until it's completed
-    let mut run_status = String::new();
+    let res = client.delete(format!("{}/secrets/ORC_IT_API_TOKEN", server_url)).bearer_auth(&token).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::NO_CONTENT);
+    let res = client.delete(format!("{}/secrets/ORC_IT_API_TOKEN", server_url)).bearer_auth(&token).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
 
-    for _ in 0..10 { // Max 10 retries
-        let res = client
-            .get(&format!("{}/runs", server_url))
-            .send()
-            .await?;
-        let runs: Vec<serde_json::Value> = res.json().await?;
-        
-        if let Some(run) = runs.into_iter().find(|r| r["job_id"] == job_id) {
-            run_status = run["status"].as_str().unwrap().to_string();
-            if run_status == "completed" || run_status == "failed" {
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_configs_are_rendered_with_run_context() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+    let dir = tempfile::tempdir()?;
+    let out_dir = dir.path().to_string_lossy().into_owned();
+
+    let payload = json!({
+        "job_name": "Daily export",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [{
+            "extractor_config": { "type": "csv", "path": "test_data.csv" },
+            "loader_config": { "type": "csv", "path": format!("{}/{{{{ job_name }}}}_{{{{ ds_nodash }}}}.csv", out_dir) }
+        }]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    let preview: serde_json::Value = client
+        .post(format!("{}/jobs/{}/preview", server_url, job_id))
+        .json(&json!({ "logical_date": "2026-10-01" }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(preview["variables"]["ds"], "2026-10-01");
+    assert_eq!(preview["variables"]["prev_success_ts"], serde_json::Value::Null);
+    assert_eq!(preview["tasks"][0]["loader_config"]["path"], format!("{}/Daily export_20261001.csv", out_dir));
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success", "{}", run["error_message"]);
+    let ds_nodash = run["created_at"].as_str().unwrap()[..10].replace('-', "");
+    assert!(dir.path().join(format!("Daily export_{}.csv", ds_nodash)).exists());
+
+    // The previous successful run is now part of the context.
+    let preview: serde_json::Value = client.post(format!("{}/jobs/{}/preview", server_url, job_id)).send().await?.json().await?;
+    assert_eq!(preview["variables"]["prev_success_ds"].as_str().unwrap().replace('-', ""), ds_nodash);
+
+    // Unknown variables are rejected when the job is created.
+    let mut invalid = payload.clone();
+    invalid["tasks"][0]["extractor_config"]["path"] = json!("data/{{ date }}.csv");
+    let res = client.post(format!("{}/jobs", server_url)).json(&invalid).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_manual_runs_take_params() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+    let dir = tempfile::tempdir()?;
+    let out_path = dir.path().join("{{ params.table }}_{{ params.limit }}.csv").to_string_lossy().into_owned();
+
+    let payload = json!({
+        "job_name": "Parameterized export",
+        "schedule": "@manual",
+        "is_active": true,
+        "params": {
+            "table": { "type": "string", "required": true },
+            "limit": { "type": "integer", "default": 2 }
+        },
+        "tasks": [{
+            "extractor_config": { "type": "csv", "path": "test_data.csv" },
+            "loader_config": { "type": "csv", "path": out_path }
+        }]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    // Parameters are checked against the job's schema before a run is queued.
+    let res = client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    let errors: serde_json::Value = res.json().await?;
+    assert_eq!(errors["errors"][0]["field"], "params.table");
+    let res = client
+        .post(format!("{}/jobs/{}/run", server_url, job_id))
+        .json(&json!({ "params": { "table": "users", "limit": "two" } }))
+        .send()
+        .await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+    let res = client
+        .post(format!("{}/jobs/{}/run", server_url, job_id))
+        .json(&json!({ "params": { "table": "users" } }))
+        .send()
+        .await?;
+    assert!(res.status().is_success());
+    let queued: serde_json::Value = res.json().await?;
+    assert_eq!(queued["params"], json!({ "table": "users", "limit": 2 }));
+
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success", "{}", run["error_message"]);
+    assert!(dir.path().join("users_2.csv").exists());
+
+    let res = client.post(format!("{}/jobs/{}/run", server_url, uuid::Uuid::new_v4())).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tasks_use_named_connections() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("warehouse.duckdb").to_string_lossy().into_owned();
+
+    // Managing connections takes a token.
+    let res = client.get(format!("{}/connections", server_url)).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let token = common::login(&client, &server_url).await?;
+
+    let res = client
+        .put(format!("{}/connections/warehouse", server_url))
+        .bearer_auth(&token)
+        .json(&json!({ "type": "duckdb", "options": { "db_path": db_path, "mode": "replace" } }))
+        .send()
+        .await?;
+    assert!(res.status().is_success());
+    let res = client
+        .put(format!("{}/connections/broken", server_url))
+        .bearer_auth(&token)
+        .json(&json!({ "type": "duckdb", "options": { "path": "x.duckdb" } }))
+        .send()
+        .await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    let errors: serde_json::Value = res.json().await?;
+    assert_eq!(errors["errors"][0]["field"], "options.db_path");
+
+    let payload = json!({
+        "job_name": "Load via connection",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [{
+            "extractor_config": { "type": "csv", "path": "test_data.csv" },
+            "loader_config": { "type": "duckdb", "connection": "warehouse", "table_name": "users" }
+        }]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success", "{}", run["error_message"]);
+    let conn = duckdb::Connection::open(&db_path)?;
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0))?;
+    assert!(count > 0);
+    drop(conn);
+
+    let outcome: serde_json::Value = client.post(format!("{}/connections/warehouse/test", server_url)).bearer_auth(&token).send().await?.json().await?;
+    assert_eq!(outcome["ok"], true, "{}", outcome["message"]);
+    client
+        .put(format!("{}/connections/api", server_url))
+        .bearer_auth(&token)
+        .json(&json!({ "type": "http", "options": { "url": format!("{}/health", server_url) } }))
+        .send()
+        .await?;
+    let outcome: serde_json::Value = client.post(format!("{}/connections/api/test", server_url)).bearer_auth(&token).send().await?.json().await?;
+    assert_eq!(outcome["ok"], true, "{}", outcome["message"]);
+
+    // Unknown connections are rejected when the job is created.
+    let mut invalid = payload.clone();
+    invalid["tasks"][0]["loader_config"]["connection"] = json!("lake");
+    let res = client.post(format!("{}/jobs", server_url)).json(&invalid).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    let errors: serde_json::Value = res.json().await?;
+    assert_eq!(errors["errors"][0]["field"], "tasks[0].loader_config.connection");
+
+    let res = client.delete(format!("{}/connections/api", server_url)).bearer_auth(&token).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::NO_CONTENT);
+    let res = client.get(format!("{}/connections/api", server_url)).bearer_auth(&token).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retried_run_keeps_one_result_per_loader() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("retry.sqlite").to_string_lossy().into_owned();
+    let marker = dir.path().join("attempted").to_string_lossy().into_owned();
+
+    // The process loader fails on the first attempt only, which makes the worker retry the run.
+    let script = format!("cat > /dev/null; [ -f '{0}' ] && exit 0; touch '{0}'; exit 1", marker);
+    let payload = json!({
+        "job_name": "Retried load",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [{
+            "extractor_config": { "type": "csv", "path": "test_data.csv" },
+            "loader_config": [
+                { "type": "sqlite", "db_path": db_path, "table_name": "users", "mode": "append" },
+                { "type": "process", "command": "sh", "args": ["-c", script] }
+            ]
+        }]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success", "{}", run["error_message"]);
+    assert!(std::path::Path::new(&marker).exists());
+    assert_eq!(run["metrics"]["attempt"], 2);
+
+    let results: Vec<serde_json::Value> = client
+        .get(format!("{}/runs/{}/results", server_url, run["run_id"].as_str().unwrap()))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let mut loaders: Vec<i64> = results.iter().map(|r| r["loader_index"].as_i64().unwrap()).collect();
+    loaders.sort();
+    assert_eq!(loaders, vec![0, 1]);
+
+    // The SQLite loader delivered on the first attempt, so the retry didn't append the rows again.
+    let mut conn = sqlx::SqliteConnection::connect(&format!("sqlite://{}", db_path)).await?;
+    let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users").fetch_one(&mut conn).await?;
+    assert_eq!(rows, 2);
+    let loaders = run["metrics"]["tasks"][0]["loaders"].as_array().unwrap();
+    let statuses: Vec<&str> = loaders.iter().map(|l| l["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses, vec!["success", "success"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_partial_http_delivery_is_not_resent() -> Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+
+    // Accepts the first batch and rejects every later one as invalid.
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let app = axum::Router::new().route(
+        "/ingest",
+        axum::routing::post(move || {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                    axum::http::StatusCode::OK
+                } else {
+                    axum::http::StatusCode::BAD_REQUEST
+                }
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let endpoint = format!("http://{}/ingest", listener.local_addr()?);
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let payload = json!({
+        "job_name": "Partial HTTP delivery",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [{
+            "extractor_config": { "type": "csv", "path": "test_data.csv" },
+            "loader_config": { "type": "http", "url": endpoint, "batch_size": 1 }
+        }]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "failed");
+
+    // One request per batch: the run wasn't retried, so the delivered batch wasn't sent twice.
+    assert_eq!(run["metrics"]["attempt"], 1);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    let loader = &run["metrics"]["tasks"][0]["loaders"][0];
+    assert_eq!(loader["rows"], 1, "{}", loader);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unknown_secret_fails_the_run_without_retrying() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+
+    let payload = json!({
+        "job_name": "Command with a missing secret",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [{
+            "command": {
+                "command": "sh",
+                "args": ["-c", "echo \"calling with $TOKEN\""],
+                "env": { "TOKEN": "${secret:ORC_IT_MISSING_TOKEN}" }
+            }
+        }]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "failed");
+    assert!(run["error_message"].as_str().unwrap().contains("Secret 'ORC_IT_MISSING_TOKEN' is not set"), "{}", run["error_message"]);
+    // The secret would still be missing on a second attempt.
+    assert_eq!(run["metrics"]["attempt"], 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_breaking_drift_fails_the_run_without_retrying() -> Result<()> {
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+    let dir = tempfile::tempdir()?;
+    let csv_path = dir.path().join("users.csv");
+    std::fs::write(&csv_path, "id,name\n1,a\n2,b\n")?;
+
+    let payload = json!({
+        "job_name": "Drift checked CSV",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [{
+            "extractor_config": { "type": "csv", "path": csv_path },
+            "fail_on_breaking_drift": true,
+            "loader_config": { "type": "duckdb", "db_path": ":memory:", "table_name": "users" }
+        }]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    let mut runs = Vec::new();
+    for contents in ["id,name\n1,a\n2,b\n", "id\n1\n2\n"] {
+        std::fs::write(&csv_path, contents)?;
+        let run: serde_json::Value = client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?.json().await?;
+        let run_id = run["run_id"].as_str().unwrap().to_string();
+        let mut run = run;
+        for _ in 0..60 {
+            run = client.get(format!("{}/runs/{}", server_url, run_id)).send().await?.json().await?;
+            if run["status"] == "success" || run["status"] == "failed" {
                 break;
             }
+            sleep(Duration::from_millis(500)).await;
         }
-        sleep(Duration::from_secs(1)).await;
+        runs.push(run);
     }
 
-    // 4. Assert the final status is "completed"
-    assert_eq!(run_status, "success");
+    assert_eq!(runs[0]["status"], "success", "{}", runs[0]["error_message"]);
+    // Dropping `name` is breaking drift, which a retry wouldn't fix.
+    let failed = &runs[1];
+    assert_eq!(failed["status"], "failed");
+    assert!(failed["error_message"].as_str().unwrap().contains("Breaking schema drift: removed [name]"), "{}", failed["error_message"]);
+    assert_eq!(failed["metrics"]["attempt"], 1);
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,69 @@
+//! The WASM transform reads its plugin directory from `PLUGIN_DIR`. Setting it changes the
+//! whole process, so this test runs in its own binary rather than alongside the other
+//! integration tests.
+
+use anyhow::Result;
+use serde_json::json;
+
+mod common;
+
+use common::wait_for_finished_run;
+
+#[tokio::test]
+async fn test_wasm_transform_from_plugin_directory() -> Result<()> {
+    let plugin_dir = tempfile::tempdir()?;
+    // Returns each batch unchanged; `alloc` bumps a pointer and grows memory when needed.
+    std::fs::write(
+        plugin_dir.path().join("passthrough.wat"),
+        r#"(module
+             (memory (export "memory") 1)
+             (global $next (mut i32) (i32.const 1024))
+             (func (export "alloc") (param $len i32) (result i32)
+               (local $ptr i32)
+               (local.set $ptr (global.get $next))
+               (global.set $next (i32.add (global.get $next) (local.get $len)))
+               (if (i32.gt_u (global.get $next) (i32.mul (memory.size) (i32.const 65536)))
+                 (then (drop (memory.grow (i32.add (i32.div_u (local.get $len) (i32.const 65536)) (i32.const 1))))))
+               (local.get $ptr))
+             (func (export "transform") (param $ptr i32) (param $len i32) (param i32) (param i32) (result i64)
+               (i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32)) (i64.extend_i32_u (local.get $len)))))"#,
+    )?;
+    // Set before the server and worker start, while this binary's only test is the only thread
+    // reading the environment.
+    std::env::set_var("PLUGIN_DIR", plugin_dir.path());
+    let server_url = common::setup().await?;
+    let client = reqwest::Client::new();
+
+    let mut payload = json!({
+        "job_name": "WASM transform",
+        "schedule": "@manual",
+        "is_active": true,
+        "tasks": [
+            {
+                "extractor_config": { "type": "csv", "path": "test_data.csv" },
+                "transform": [{ "op": "wasm", "module": "missing.wasm" }],
+                "loader_config": { "type": "duckdb", "db_path": ":memory:", "table_name": "from_wasm" }
+            }
+        ]
+    });
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    let body = res.json::<serde_json::Value>().await?;
+    assert_eq!(body["errors"][0]["field"], "tasks[0].transform[0].module");
+
+    payload["tasks"][0]["transform"] = json!([
+        { "op": "wasm", "module": "passthrough.wat", "max_memory_mb": 8, "fuel": 1000000 },
+        { "op": "filter", "expr": "id > 1" }
+    ]);
+    let res = client.post(format!("{}/jobs", server_url)).json(&payload).send().await?;
+    assert!(res.status().is_success());
+    let job_id = res.json::<serde_json::Value>().await?["job_id"].as_str().unwrap().to_string();
+
+    client.post(format!("{}/jobs/{}/run", server_url, job_id)).send().await?;
+    let run = wait_for_finished_run(&client, &server_url, &job_id).await?;
+    assert_eq!(run["status"], "success");
+    assert_eq!(run["metrics"]["tasks"][0]["rows_extracted"], 2);
+    assert_eq!(run["metrics"]["tasks"][0]["rows_transformed"], 1);
+
+    Ok(())
+}
@@ -4,13 +4,14 @@ CREATE SCHEMA IF NOT EXISTS analytics;
 -- Staging schema for raw data
 CREATE SCHEMA IF NOT EXISTS staging;
 
--- Job results aggregation view
+-- Job results aggregation view (job_results is populated by the worker after every load)
 CREATE OR REPLACE VIEW analytics.job_summary AS
 SELECT 
     job_id,
-    COUNT(*) as execution_count,
+    COUNT(DISTINCT run_id) as execution_count,
     AVG(row_count) as avg_rows,
+    SUM(bytes_written) as total_bytes,
     MIN(created_at) as first_execution,
     MAX(created_at) as last_execution
-FROM staging.job_metadata
+FROM public.job_results
 GROUP BY job_id;
\ No newline at end of file